@@ -22,31 +22,15 @@ async fn main() -> Result<()> {
         "012345678901",
     ];
 
+    let tracking_numbers: Vec<String> = tracking_numbers.into_iter().map(String::from).collect();
+
+    // At most 4 packages are polled at once; results come back in input order
     println!(
         "Tracking {} packages concurrently...",
         tracking_numbers.len()
     );
     let start = Instant::now();
-
-    // Spawn concurrent tasks
-    let handles: Vec<_> = tracking_numbers
-        .iter()
-        .map(|num| {
-            let client = client.clone(); // Cheap clone (Arc)
-            let num = num.to_string();
-            tokio::spawn(async move { client.track(&num, carriers::AUTO).await })
-        })
-        .collect();
-
-    // Wait for all tasks to complete
-    let mut results = Vec::new();
-    for handle in handles {
-        match handle.await? {
-            Ok(response) => results.push(response),
-            Err(e) => eprintln!("Error tracking package: {}", e),
-        }
-    }
-
+    let results = client.track_all(&tracking_numbers, carriers::AUTO, 4).await;
     let elapsed = start.elapsed();
 
     println!("\n=== Results ===");
@@ -57,16 +41,11 @@ async fn main() -> Result<()> {
     );
 
     // Display results
-    for (i, response) in results.iter().enumerate() {
-        println!(
-            "\n[{}] Status: {} - {}",
-            i + 1,
-            response.meta.code,
-            response.meta.message
-        );
-        for shipment in &response.shipments {
-            println!("  Tracking: {}", shipment.number);
-            println!("  Code: {}", shipment.code);
+    for (i, (number, result)) in results.iter().enumerate() {
+        println!("\n[{}] Tracking: {}", i + 1, number);
+        match result {
+            Ok(shipment) => println!("  Code: {}", shipment.code),
+            Err(e) => eprintln!("  Error tracking package: {}", e),
         }
     }
 
@@ -13,14 +13,10 @@ async fn main() -> Result<()> {
         println!("\nTracking: {}", shipment.number);
 
         if let Some(details) = &shipment.shipment {
-            let latest = details.latest_event.as_ref().or_else(|| {
-                details
-                    .tracking
-                    .as_ref()
-                    .and_then(|t| t.providers.as_ref())
-                    .and_then(|p| p.first())
-                    .and_then(|p| p.events.first())
-            });
+            let latest = details
+                .latest_event
+                .as_ref()
+                .or_else(|| details.events().first().copied());
 
             if let Some(event) = latest {
                 let state = event.tracking_state();
@@ -1,38 +1,118 @@
+use std::collections::HashMap;
 use std::env;
+use std::fmt::Write as _;
 use std::net::SocketAddr;
-use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
 use axum::{
     Router,
-    extract::State,
-    http::StatusCode,
+    body::Body,
+    extract::{
+        Path, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header},
     response::{IntoResponse, Json, Response},
-    routing::{get, post},
+    routing::{delete, get, post},
 };
+use futures::future::{BoxFuture, Shared};
+use futures::{FutureExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
 use tower::ServiceBuilder;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    trace::TraceLayer,
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use track17_rs::types::TrackingEvent;
-use track17_rs::{Shipment, Track17Client, carriers, format_location};
+use track17_rs::types::{ParamV2, TrackingEvent};
+use track17_rs::zipcode::country_name;
+use track17_rs::{
+    ProxyConfig, Shipment, StateCategory, Track17Client, Track17Config, TrackError, TrackTarget,
+    TrackingState, carriers, format_location,
+};
 
 /// Server configuration
 struct ServerConfig {
     port: u16,
+    /// Generate credentials at startup (`WARM_UP=true`)
+    warm_up: bool,
+    /// Serve resolved results from memory for this long (`RESULT_CACHE_TTL`, seconds)
+    result_cache_ttl: Option<Duration>,
+    /// Origins allowed by CORS (`ALLOWED_ORIGINS`, comma-separated); any origin when unset
+    allowed_origins: Option<Vec<HeaderValue>>,
+    /// How long an `Idempotency-Key` keeps its result (`IDEMPOTENCY_WINDOW`, seconds)
+    idempotency_window: Duration,
 }
 
 impl ServerConfig {
-    fn from_env() -> Self {
-        Self {
+    fn from_env() -> Result<Self> {
+        Ok(Self {
             port: env::var("PORT")
                 .ok()
                 .and_then(|p| p.parse().ok())
                 .unwrap_or(3000),
-        }
+            warm_up: env::var("WARM_UP")
+                .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false),
+            result_cache_ttl: env::var("RESULT_CACHE_TTL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            allowed_origins: env::var("ALLOWED_ORIGINS")
+                .ok()
+                .map(|v| parse_allowed_origins(&v))
+                .transpose()?,
+            idempotency_window: env::var("IDEMPOTENCY_WINDOW")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_IDEMPOTENCY_WINDOW),
+        })
+    }
+}
+
+/// Parse a comma-separated origin list (`https://app.example.com,http://localhost:8080`)
+fn parse_allowed_origins(value: &str) -> Result<Vec<HeaderValue>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            let host = origin
+                .strip_prefix("https://")
+                .or_else(|| origin.strip_prefix("http://"))
+                .filter(|host| !host.is_empty() && !host.contains('/'));
+            anyhow::ensure!(
+                host.is_some(),
+                "Invalid origin '{}' in ALLOWED_ORIGINS (expected scheme://host[:port])",
+                origin
+            );
+            HeaderValue::from_str(origin)
+                .with_context(|| format!("Invalid origin '{}' in ALLOWED_ORIGINS", origin))
+        })
+        .collect()
+}
+
+/// Restrict CORS to `allowed_origins`, or allow any origin when unset
+fn cors_layer(allowed_origins: Option<Vec<HeaderValue>>) -> CorsLayer {
+    match allowed_origins {
+        Some(origins) => CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods([Method::GET, Method::POST, Method::DELETE])
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                HeaderName::from_static(IDEMPOTENCY_KEY),
+            ]),
+        None => CorsLayer::permissive(),
     }
 }
 
@@ -41,15 +121,141 @@ impl ServerConfig {
 struct AppState {
     client: Arc<Track17Client>,
     metrics: Arc<Metrics>,
+    idempotency: Arc<IdempotencyCache>,
+}
+
+const DEFAULT_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(300);
+
+/// A `/api/track` operation shared by every request carrying the same key
+type SharedTrack = Shared<BoxFuture<'static, Result<TrackingResponse, String>>>;
+
+/// Operations keyed by `Idempotency-Key`, so a client retrying after a timeout
+/// joins the original poll (or gets its result) instead of starting another.
+///
+/// Failed operations are forgotten, so a retry after an error polls again.
+struct IdempotencyCache {
+    window: Duration,
+    entries: Mutex<HashMap<String, IdempotencyEntry>>,
+}
+
+struct IdempotencyEntry {
+    started: Instant,
+    /// The request the key was first used with
+    fingerprint: String,
+    operation: SharedTrack,
+}
+
+impl IdempotencyCache {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The operation for `key`, starting one with `start` if the key is new,
+    /// expired or its operation failed.
+    ///
+    /// Reusing a key for a request with a different `fingerprint` is rejected
+    /// rather than answered with the other request's result.
+    fn get_or_start(
+        &self,
+        key: &str,
+        fingerprint: String,
+        start: impl FnOnce() -> BoxFuture<'static, Result<TrackingResponse, String>>,
+    ) -> Result<SharedTrack, ApiError> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| {
+            now.duration_since(entry.started) < self.window
+                && !matches!(entry.operation.peek(), Some(Err(_)))
+        });
+        let entry = entries
+            .entry(key.to_string())
+            .or_insert_with(|| IdempotencyEntry {
+                started: now,
+                fingerprint: fingerprint.clone(),
+                operation: start().shared(),
+            });
+        if entry.fingerprint != fingerprint {
+            return Err(ApiError::IdempotencyKeyReused);
+        }
+        Ok(entry.operation.clone())
+    }
 }
 
 /// Server metrics
 struct Metrics {
     total_requests: AtomicU64,
     requests_in_flight: AtomicU64,
+    credential_refreshes: AtomicU64,
+    request_duration: Histogram,
     start_time: Instant,
 }
 
+/// Upper bounds (seconds) of the request duration histogram buckets
+const DURATION_BUCKETS: [f64; 9] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// Lock-free Prometheus-style histogram
+struct Histogram {
+    /// Per-bucket (non-cumulative) counts; the last slot is `+Inf`
+    buckets: [AtomicU64; DURATION_BUCKETS.len() + 1],
+    sum_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            sum_micros: AtomicU64::new(0),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(DURATION_BUCKETS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn start_timer(&self) -> RequestTimer<'_> {
+        RequestTimer(self, Instant::now())
+    }
+
+    /// Render in Prometheus text exposition format
+    fn render(&self, name: &str, help: &str, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let le = DURATION_BUCKETS
+                .get(i)
+                .map_or("+Inf".to_string(), |le| le.to_string());
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, cumulative);
+        }
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, self.count.load(Ordering::Relaxed));
+    }
+}
+
+/// RAII guard recording a request's duration on drop
+struct RequestTimer<'a>(&'a Histogram, Instant);
+
+impl Drop for RequestTimer<'_> {
+    fn drop(&mut self) {
+        self.0.observe(self.1.elapsed());
+    }
+}
+
 /// RAII guard for tracking in-flight requests
 struct RequestGuard<'a>(&'a AtomicU64);
 
@@ -71,19 +277,31 @@ async fn main() -> Result<()> {
         .init();
 
     // Read configuration from environment
-    let config = ServerConfig::from_env();
+    let config = ServerConfig::from_env()?;
 
     // Initialize shared Track17Client
     tracing::info!("Initializing Track17 client...");
-    let track_client = Arc::new(
-        Track17Client::new()
-            .await
-            .context("Failed to initialize Track17 client")?,
-    );
+    let track_client = Track17Client::with_config(Track17Config {
+        proxy_from_env: true,
+        result_cache_ttl: config.result_cache_ttl,
+        ..Default::default()
+    })
+    .await
+    .context("Failed to initialize Track17 client")?;
     tracing::info!("Track17 client initialized successfully");
 
+    if config.warm_up {
+        tracing::info!("Warming up credentials...");
+        match track_client.warm_up().await {
+            Ok(()) => tracing::info!("Credentials ready"),
+            // Not fatal: the first request will retry generation
+            Err(e) => tracing::warn!("Credential warm-up failed: {:#}", e),
+        }
+    }
+
     // Build Axum app with routes
-    let app = build_app(track_client);
+    let app = build_app_with_idempotency_window(track_client, config.idempotency_window)
+        .layer(cors_layer(config.allowed_origins));
 
     // Bind server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
@@ -101,28 +319,48 @@ async fn main() -> Result<()> {
 }
 
 /// Build the Axum application with routes and middleware
-fn build_app(client: Arc<Track17Client>) -> Router {
+fn build_app(client: Track17Client) -> Router {
+    build_app_with_idempotency_window(client, DEFAULT_IDEMPOTENCY_WINDOW)
+}
+
+fn build_app_with_idempotency_window(client: Track17Client, window: Duration) -> Router {
     let metrics = Arc::new(Metrics {
         total_requests: AtomicU64::new(0),
         requests_in_flight: AtomicU64::new(0),
+        credential_refreshes: AtomicU64::new(0),
+        request_duration: Histogram::new(),
         start_time: Instant::now(),
     });
 
-    let state = AppState { client, metrics };
+    let refreshes = metrics.clone();
+    let client = Arc::new(client.on_credential_event(move |_| {
+        refreshes
+            .credential_refreshes
+            .fetch_add(1, Ordering::Relaxed);
+    }));
+
+    let state = AppState {
+        client,
+        metrics,
+        idempotency: Arc::new(IdempotencyCache::new(window)),
+    };
 
     Router::new()
         // Health check
         .route("/health", get(health_check))
+        .route("/ready", get(readiness_check))
         // API routes
         .route("/api/track", post(track_single))
         .route("/api/track/batch", post(track_batch))
+        .route("/api/track/batch/v2", post(track_batch_v2))
+        .route("/api/track/bulk", post(track_bulk))
+        .route("/api/track/cache/:number", delete(invalidate_cached))
         .route("/api/metrics", get(get_metrics))
+        .route("/metrics", get(get_prometheus_metrics))
+        .route("/ws/track", get(ws_track))
+        .route("/openapi.json", get(openapi_json))
         // Middleware
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(CorsLayer::permissive()),
-        )
+        .layer(ServiceBuilder::new().layer(TraceLayer::new_for_http()))
         .with_state(state)
 }
 
@@ -134,15 +372,501 @@ async fn health_check() -> Json<HealthResponse> {
     })
 }
 
+/// How long the proxy readiness probe waits for a TCP connection
+const PROXY_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Readiness: whether each dependency tracking needs is working.
+///
+/// `cdn` and `v8` come from generating credentials (a no-op while cached ones
+/// are valid), `proxy` from connecting to it. 503 if any component failed.
+async fn readiness_check(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let proxy = match state.client.proxy() {
+        Some(proxy) => check_proxy(proxy).await,
+        None => ComponentStatus::skipped(),
+    };
+    let (cdn, v8) = match state.client.warm_up().await {
+        Ok(()) => (ComponentStatus::ok(), ComponentStatus::ok()),
+        Err(e) => match e.downcast_ref::<TrackError>() {
+            // Assets never arrived, so the sign module wasn't tried
+            Some(TrackError::AssetFetch { .. } | TrackError::AssetDecodeFailed { .. }) => {
+                (ComponentStatus::error(&e), ComponentStatus::skipped())
+            }
+            _ => (ComponentStatus::ok(), ComponentStatus::error(&e)),
+        },
+    };
+
+    let ready = [&cdn, &v8, &proxy]
+        .iter()
+        .all(|component| component.status != ComponentStatus::ERROR);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ReadinessResponse {
+            ready,
+            cdn,
+            v8,
+            proxy,
+        }),
+    )
+}
+
+async fn check_proxy(proxy: &ProxyConfig) -> ComponentStatus {
+    let connect = tokio::net::TcpStream::connect(proxy.to_host_port());
+    match tokio::time::timeout(PROXY_CHECK_TIMEOUT, connect).await {
+        Ok(Ok(_)) => ComponentStatus::ok(),
+        Ok(Err(e)) => ComponentStatus::error(&e),
+        Err(_) => {
+            ComponentStatus::error(&format!("no connection within {:?}", PROXY_CHECK_TIMEOUT))
+        }
+    }
+}
+
+/// OpenAPI 3.0 description of the HTTP API
+async fn openapi_json() -> Json<serde_json::Value> {
+    Json(openapi_spec())
+}
+
+/// Hand-written to match the request/response structs below; keep in sync
+/// when adding fields.
+fn openapi_spec() -> serde_json::Value {
+    use serde_json::json;
+
+    let statuses: Vec<String> = TrackingState::ALL.iter().map(|s| s.to_string()).collect();
+    let categories: Vec<String> = StateCategory::ALL.iter().map(|c| c.to_string()).collect();
+    let json_body = |schema: &str| json!({"content": {"application/json": {"schema": {"$ref": format!("#/components/schemas/{}", schema)}}}});
+    let error = |description: &str| {
+        let mut response = json_body("Error");
+        response["description"] = json!(description);
+        response
+    };
+    let ok = |description: &str, schema: &str| {
+        let mut response = json_body(schema);
+        response["description"] = json!(description);
+        response
+    };
+
+    let paths = json!({
+        "/health": {
+            "get": {
+                "summary": "Health check",
+                "responses": {"200": ok("Server is up", "HealthResponse")},
+            }
+        },
+        "/ready": {
+            "get": {
+                "summary": "Per-component readiness (CDN, V8 sign generation, proxy)",
+                "responses": {
+                    "200": ok("Every component is ok or skipped", "ReadinessResponse"),
+                    "503": ok("At least one component failed", "ReadinessResponse"),
+                },
+            }
+        },
+        "/api/track": {
+            "post": {
+                "summary": "Track a single package",
+                "parameters": [{
+                    "name": "Idempotency-Key",
+                    "in": "header",
+                    "required": false,
+                    "description": "Requests with the same key share one operation and result; failures are not kept, and reusing a key for a different request is a 422",
+                    "schema": {"type": "string"},
+                }],
+                "requestBody": {"required": true, "content": json_body("TrackRequest")["content"]},
+                "responses": {
+                    "200": ok("Tracking data", "TrackResponse"),
+                    "300": ok("Carrier is ambiguous (resolve_carrier: false)", "AmbiguousCarrierError"),
+                    "404": error("No tracking data (or still pending)"),
+                    "422": ok("Carrier requires additional parameters (or Idempotency-Key reused for a different request)", "MissingParamsError"),
+                    "500": error("Tracking failed"),
+                },
+            }
+        },
+        "/api/track/batch": {
+            "post": {
+                "summary": "Track multiple packages with one carrier",
+                "requestBody": {"required": true, "content": json_body("BatchTrackRequest")["content"]},
+                "responses": {
+                    "200": ok("Tracking data", "BatchTrackResponse"),
+                    "207": ok("Some packages still pending (see `incomplete`)", "BatchTrackResponse"),
+                    "400": error("Invalid request"),
+                    "500": error("Tracking failed"),
+                },
+            }
+        },
+        "/api/track/batch/v2": {
+            "post": {
+                "summary": "Track multiple packages, each with its own carrier",
+                "requestBody": {"required": true, "content": json_body("BatchTrackV2Request")["content"]},
+                "responses": {
+                    "200": ok("Per-item results in input order", "BatchTrackV2Response"),
+                    "207": ok("Some packages still pending", "BatchTrackV2Response"),
+                    "400": error("Invalid request"),
+                    "500": error("Tracking failed"),
+                },
+            }
+        },
+        "/api/track/bulk": {
+            "post": {
+                "summary": "Track packages from an NDJSON body, streaming NDJSON results",
+                "description": "Each line is a tracking number, a JSON string, or a BatchTrackItem. \
+                    One TrackData line is streamed per package as it resolves; failures and \
+                    invalid lines are streamed as BatchItemResult lines.",
+                "requestBody": {"required": true, "content": {"application/x-ndjson": {"schema": {"type": "string"}}}},
+                "responses": {"200": {
+                    "description": "One JSON object per line",
+                    "content": {"application/x-ndjson": {"schema": {"type": "string"}}},
+                }},
+            }
+        },
+        "/api/track/cache/{number}": {
+            "delete": {
+                "summary": "Drop one number from the result cache",
+                "parameters": [{
+                    "name": "number",
+                    "in": "path",
+                    "required": true,
+                    "schema": {"type": "string"},
+                }],
+                "responses": {"204": {"description": "Removed (or was not cached)"}},
+            }
+        },
+        "/api/metrics": {
+            "get": {
+                "summary": "Server metrics",
+                "responses": {"200": ok("Counters", "MetricsResponse")},
+            }
+        },
+        "/metrics": {
+            "get": {
+                "summary": "Server metrics in Prometheus text format",
+                "responses": {"200": {
+                    "description": "Prometheus exposition format",
+                    "content": {"text/plain": {"schema": {"type": "string"}}},
+                }},
+            }
+        },
+        "/ws/track": {
+            "get": {
+                "summary": "Stream tracking updates over a WebSocket",
+                "description": "Send `{\"subscribe\": [\"NUM1\"], \"carrier\": \"auto\"}` frames; \
+                    receive one TrackData frame per package, then `{\"done\": true}`.",
+                "responses": {"101": {"description": "Switching protocols"}},
+            }
+        },
+    });
+
+    let schemas = json!({
+        "TrackingState": {"type": "string", "enum": statuses},
+        "StateCategory": {"type": "string", "enum": categories},
+        "Params": {
+            "type": "object",
+            "description": "Extra carrier inputs keyed by `required_params[].key`",
+            "additionalProperties": true,
+        },
+        "TrackRequest": {
+            "type": "object",
+            "required": ["tracking_number"],
+            "properties": {
+                "tracking_number": {"type": "string"},
+                "carrier_code": {"type": "integer", "format": "uint32", "default": 0},
+                "resolve_locations": {"type": "boolean", "default": true},
+                "params": {"$ref": "#/components/schemas/Params"},
+                "resolve_carrier": {"type": "boolean", "default": true},
+                "tag": {"type": "string"},
+            },
+        },
+        "BatchTrackRequest": {
+            "type": "object",
+            "required": ["tracking_numbers"],
+            "properties": {
+                "tracking_numbers": {"type": "array", "items": {"type": "string"}},
+                "carrier_code": {"type": "integer", "format": "uint32", "default": 0},
+                "resolve_locations": {"type": "boolean", "default": true},
+                "resolve_carrier": {"type": "boolean", "default": true},
+            },
+        },
+        "BatchTrackItem": {
+            "type": "object",
+            "required": ["number"],
+            "properties": {
+                "number": {"type": "string"},
+                "carrier": {"type": "string", "enum": ["auto", "fedex", "ups", "usps", "dhl"]},
+                "carrier_code": {"type": "integer", "format": "uint32"},
+                "params": {"$ref": "#/components/schemas/Params"},
+                "tag": {"type": "string"},
+            },
+        },
+        "BatchTrackV2Request": {
+            "type": "object",
+            "required": ["items"],
+            "properties": {
+                "items": {"type": "array", "items": {"$ref": "#/components/schemas/BatchTrackItem"}},
+                "resolve_locations": {"type": "boolean", "default": true},
+            },
+        },
+        "TrackResponse": {
+            "type": "object",
+            "required": ["success", "data"],
+            "properties": {
+                "success": {"type": "boolean"},
+                "data": {"$ref": "#/components/schemas/TrackData"},
+            },
+        },
+        "BatchTrackResponse": {
+            "type": "object",
+            "required": ["success", "data"],
+            "properties": {
+                "success": {"type": "boolean"},
+                "data": {"type": "array", "items": {"$ref": "#/components/schemas/TrackData"}},
+                "incomplete": {"type": "array", "items": {"type": "string"}},
+            },
+        },
+        "BatchItemResult": {
+            "type": "object",
+            "required": ["tracking_number", "success"],
+            "properties": {
+                "tracking_number": {"type": "string"},
+                "success": {"type": "boolean"},
+                "data": {"$ref": "#/components/schemas/TrackData"},
+                "error": {"type": "string"},
+            },
+        },
+        "BatchTrackV2Response": {
+            "type": "object",
+            "required": ["success", "data"],
+            "properties": {
+                "success": {"type": "boolean"},
+                "data": {"type": "array", "items": {"$ref": "#/components/schemas/BatchItemResult"}},
+            },
+        },
+        "TrackData": {
+            "type": "object",
+            "required": ["tracking_number", "carrier", "status", "category", "progress", "all_events", "milestones"],
+            "properties": {
+                "tracking_number": {"type": "string"},
+                "carrier": {"type": "integer", "format": "uint32"},
+                "status": {"$ref": "#/components/schemas/TrackingState"},
+                "category": {"$ref": "#/components/schemas/StateCategory"},
+                "progress": {"type": "integer", "minimum": 0, "maximum": 100},
+                "latest_event": {"allOf": [{"$ref": "#/components/schemas/EventData"}], "nullable": true},
+                "all_events": {"type": "array", "items": {"$ref": "#/components/schemas/EventData"}},
+                "milestones": {"type": "array", "items": {"$ref": "#/components/schemas/MilestoneData"}},
+                "origin_country": {"$ref": "#/components/schemas/CountryData"},
+                "destination_country": {"$ref": "#/components/schemas/CountryData"},
+                "carrier_url": {"type": "string", "format": "uri"},
+                "transition": {"$ref": "#/components/schemas/TransitionData"},
+                "event_fingerprint": {
+                    "type": "string",
+                    "pattern": "^[0-9a-f]{16}$",
+                    "description": "Changes when a newer event arrives; compare between polls",
+                },
+                "candidate_carriers": {"type": "array", "items": {"$ref": "#/components/schemas/CarrierOption"}},
+                "tag": {"type": "string"},
+            },
+        },
+        "EventData": {
+            "type": "object",
+            "required": ["time", "description"],
+            "properties": {
+                "time": {"type": "string"},
+                "description": {"type": "string"},
+                "description_original": {"type": "string"},
+                "location": {"type": "string", "nullable": true},
+                "raw_location": {"type": "string", "nullable": true},
+            },
+        },
+        "MilestoneData": {
+            "type": "object",
+            "required": ["status"],
+            "properties": {
+                "status": {"type": "string"},
+                "time": {"type": "string", "nullable": true},
+            },
+        },
+        "TransitionData": {
+            "type": "object",
+            "description": "Recent status change (`prior_status` to current)",
+            "required": ["from", "to"],
+            "properties": {
+                "from": {"$ref": "#/components/schemas/TrackingState"},
+                "to": {"$ref": "#/components/schemas/TrackingState"},
+            },
+        },
+        "CountryData": {
+            "type": "object",
+            "required": ["code"],
+            "properties": {
+                "code": {"type": "string", "example": "US"},
+                "name": {"type": "string", "nullable": true},
+            },
+        },
+        "ParamV2": {
+            "type": "object",
+            "properties": {
+                "key": {"type": "string"},
+                "input_type": {"type": "string"},
+                "example": {"type": "string"},
+                "regex": {"type": "string"},
+                "options": {"type": "array", "items": {}},
+            },
+        },
+        "Error": {
+            "type": "object",
+            "required": ["success", "error"],
+            "properties": {
+                "success": {"type": "boolean", "enum": [false]},
+                "error": {"type": "string"},
+            },
+        },
+        "CarrierOption": {
+            "type": "object",
+            "required": ["carrier_code"],
+            "properties": {
+                "carrier_code": {"type": "integer", "format": "uint32"},
+                "carrier_name": {"type": "string"},
+            },
+        },
+        "AmbiguousCarrierError": {
+            "type": "object",
+            "required": ["success", "error", "candidate_carriers"],
+            "properties": {
+                "success": {"type": "boolean", "enum": [false]},
+                "error": {"type": "string"},
+                "candidate_carriers": {"type": "array", "items": {"$ref": "#/components/schemas/CarrierOption"}},
+            },
+        },
+        "MissingParamsError": {
+            "type": "object",
+            "required": ["success", "error", "required_params"],
+            "properties": {
+                "success": {"type": "boolean", "enum": [false]},
+                "error": {"type": "string"},
+                "required_params": {"type": "array", "items": {"$ref": "#/components/schemas/ParamV2"}},
+            },
+        },
+        "HealthResponse": {
+            "type": "object",
+            "properties": {
+                "status": {"type": "string"},
+                "version": {"type": "string"},
+            },
+        },
+        "ComponentStatus": {
+            "type": "object",
+            "required": ["status"],
+            "properties": {
+                "status": {"type": "string", "enum": ["ok", "error", "skipped"]},
+                "error": {"type": "string"},
+            },
+        },
+        "ReadinessResponse": {
+            "type": "object",
+            "required": ["ready", "cdn", "v8", "proxy"],
+            "properties": {
+                "ready": {"type": "boolean"},
+                "cdn": {"$ref": "#/components/schemas/ComponentStatus"},
+                "v8": {"$ref": "#/components/schemas/ComponentStatus"},
+                "proxy": {"$ref": "#/components/schemas/ComponentStatus"},
+            },
+        },
+        "MetricsResponse": {
+            "type": "object",
+            "properties": {
+                "total_requests": {"type": "integer"},
+                "requests_in_flight": {"type": "integer"},
+                "uptime_seconds": {"type": "integer"},
+            },
+        },
+    });
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "track17-rs",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {"schemas": schemas},
+    })
+}
+
 #[derive(Serialize)]
 struct HealthResponse {
     status: String,
     version: String,
 }
 
+#[derive(Serialize)]
+struct ReadinessResponse {
+    ready: bool,
+    /// Tracking page and JS assets can be fetched
+    cdn: ComponentStatus,
+    /// The sign module initializes and produces a sign
+    v8: ComponentStatus,
+    /// The configured proxy accepts connections (`skipped` when none)
+    proxy: ComponentStatus,
+}
+
+#[derive(Serialize)]
+struct ComponentStatus {
+    /// `ok`, `error`, or `skipped` (not configured, or an earlier step failed)
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl ComponentStatus {
+    const ERROR: &'static str = "error";
+
+    fn ok() -> Self {
+        Self {
+            status: "ok",
+            error: None,
+        }
+    }
+
+    fn skipped() -> Self {
+        Self {
+            status: "skipped",
+            error: None,
+        }
+    }
+
+    fn error(error: &dyn std::fmt::Display) -> Self {
+        Self {
+            status: Self::ERROR,
+            error: Some(format!("{:#}", error)),
+        }
+    }
+}
+
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Run tracking on its own task, cancelling it if the request is dropped.
+///
+/// Axum drops the handler future when the HTTP client disconnects; the drop
+/// guard then cancels the token so the polling loop stops instead of running
+/// to completion for nobody.
+async fn track_cancellable(
+    client: Arc<Track17Client>,
+    targets: Vec<TrackTarget>,
+) -> anyhow::Result<TrackingResponse> {
+    let cancel = CancellationToken::new();
+    let _cancel_on_drop = cancel.clone().drop_guard();
+
+    tokio::spawn(async move { client.track_targets_with_cancel(&targets, &cancel).await })
+        .await
+        .context("Tracking task panicked")?
+}
+
 /// Track a single package
 async fn track_single(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(request): Json<TrackRequest>,
 ) -> Result<Json<TrackResponse>, ApiError> {
     // Increment metrics
@@ -154,6 +878,7 @@ async fn track_single(
 
     // Ensure we decrement on exit
     let _guard = RequestGuard(&state.metrics.requests_in_flight);
+    let _timer = state.metrics.request_duration.start_timer();
 
     let carrier_code = request.carrier_code.unwrap_or(carriers::AUTO);
 
@@ -164,24 +889,67 @@ async fn track_single(
     );
 
     // Call tracking client
-    let response = state
-        .client
-        .track(&request.tracking_number, carrier_code)
-        .await
-        .map_err(|e| {
-            tracing::error!("Tracking error: {}", e);
-            ApiError::InternalError(e.to_string())
-        })?;
+    let mut target = TrackTarget::new(request.tracking_number.as_str(), carrier_code);
+    target.params = request.params.clone();
+    target.retry_suggestions = request.resolve_carrier;
+    target.tag = request.tag.clone();
+    let start = || {
+        let client = state.client.clone();
+        async move {
+            track_cancellable(client, vec![target])
+                .await
+                .map_err(|e| e.to_string())
+        }
+        .boxed()
+    };
+    // Retries with the same key share one operation
+    let idempotency_key = headers
+        .get(IDEMPOTENCY_KEY)
+        .and_then(|v| v.to_str().ok())
+        .filter(|key| !key.is_empty());
+    let response = match idempotency_key {
+        Some(key) => {
+            let fingerprint = serde_json::json!({
+                "tracking_number": request.tracking_number,
+                "carrier_code": carrier_code,
+                "params": request.params,
+                "resolve_carrier": request.resolve_carrier,
+                "tag": request.tag,
+            })
+            .to_string();
+            state
+                .idempotency
+                .get_or_start(key, fingerprint, start)?
+                .await
+        }
+        None => start().await,
+    }
+    .map_err(|e| {
+        tracing::error!("Tracking error: {}", e);
+        ApiError::InternalError(e)
+    })?;
 
     // Transform response
-    let shipment = response
-        .shipments
-        .first()
-        .ok_or_else(|| ApiError::NotFound("No tracking data found for this package".to_string()))?;
+    let shipment = response.shipments.first().ok_or_else(|| {
+        if response.is_complete() {
+            ApiError::NotFound("No tracking data found for this package".to_string())
+        } else {
+            ApiError::NotFound("Tracking still pending for this package".to_string())
+        }
+    })?;
+
+    if shipment.needs_params() {
+        return Err(ApiError::MissingParams(shipment.required_params().to_vec()));
+    }
+
+    let candidates = CarrierOption::candidates(shipment);
+    if !candidates.is_empty() {
+        return Err(ApiError::AmbiguousCarrier(candidates));
+    }
 
     Ok(Json(TrackResponse {
         success: true,
-        data: TrackData::from_shipment(shipment),
+        data: TrackData::from_shipment(shipment, request.resolve_locations),
     }))
 }
 
@@ -190,6 +958,27 @@ struct TrackRequest {
     tracking_number: String,
     #[serde(default)]
     carrier_code: Option<u32>,
+    /// Resolve postal codes in event locations to "City, ST" (default: true)
+    #[serde(default = "default_resolve_locations")]
+    resolve_locations: bool,
+    /// Extra carrier inputs (see `required_params` in 422 responses)
+    #[serde(default)]
+    params: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Retry ambiguous auto-detection with a suggested carrier (default: true).
+    /// When false, ambiguous numbers get a 300 listing `candidate_carriers`.
+    #[serde(default = "default_resolve_carrier")]
+    resolve_carrier: bool,
+    /// Caller's label for the package, echoed back in `data.tag`
+    #[serde(default)]
+    tag: Option<String>,
+}
+
+fn default_resolve_locations() -> bool {
+    true
+}
+
+fn default_resolve_carrier() -> bool {
+    true
 }
 
 #[derive(Serialize)]
@@ -198,17 +987,27 @@ struct TrackResponse {
     data: TrackData,
 }
 
+/// Drop one number from the result cache so the next request refetches it
+async fn invalidate_cached(
+    State(state): State<AppState>,
+    Path(number): Path<String>,
+) -> StatusCode {
+    state.client.invalidate_number(&number);
+    StatusCode::NO_CONTENT
+}
+
 /// Track multiple packages (batch)
 async fn track_batch(
     State(state): State<AppState>,
     Json(request): Json<BatchTrackRequest>,
-) -> Result<Json<BatchTrackResponse>, ApiError> {
+) -> Result<(StatusCode, Json<BatchTrackResponse>), ApiError> {
     state.metrics.total_requests.fetch_add(1, Ordering::Relaxed);
     state
         .metrics
         .requests_in_flight
         .fetch_add(1, Ordering::Relaxed);
     let _guard = RequestGuard(&state.metrics.requests_in_flight);
+    let _timer = state.metrics.request_duration.start_timer();
 
     if request.tracking_numbers.is_empty() {
         return Err(ApiError::BadRequest(
@@ -224,10 +1023,16 @@ async fn track_batch(
         carrier_code
     );
 
-    // Use existing track_multiple method (already concurrent!)
-    let response = state
-        .client
-        .track_multiple(&request.tracking_numbers, carrier_code)
+    let targets = request
+        .tracking_numbers
+        .iter()
+        .map(|num| {
+            let mut target = TrackTarget::new(num.as_str(), carrier_code);
+            target.retry_suggestions = request.resolve_carrier;
+            target
+        })
+        .collect();
+    let response = track_cancellable(state.client.clone(), targets)
         .await
         .map_err(|e| {
             tracing::error!("Batch tracking error: {}", e);
@@ -237,13 +1042,26 @@ async fn track_batch(
     let data = response
         .shipments
         .iter()
-        .map(TrackData::from_shipment)
+        .map(|s| TrackData::from_shipment(s, request.resolve_locations))
         .collect();
 
-    Ok(Json(BatchTrackResponse {
-        success: true,
-        data,
-    }))
+    Ok((
+        partial_status(&response.incomplete),
+        Json(BatchTrackResponse {
+            success: true,
+            data,
+            incomplete: response.incomplete,
+        }),
+    ))
+}
+
+/// 207 Multi-Status when some numbers were still pending, 200 otherwise
+fn partial_status(incomplete: &[String]) -> StatusCode {
+    if incomplete.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::MULTI_STATUS
+    }
 }
 
 #[derive(Deserialize)]
@@ -251,12 +1069,492 @@ struct BatchTrackRequest {
     tracking_numbers: Vec<String>,
     #[serde(default)]
     carrier_code: Option<u32>,
+    #[serde(default = "default_resolve_locations")]
+    resolve_locations: bool,
+    /// When false, ambiguous numbers are returned with `candidate_carriers`
+    #[serde(default = "default_resolve_carrier")]
+    resolve_carrier: bool,
 }
 
 #[derive(Serialize)]
 struct BatchTrackResponse {
     success: bool,
     data: Vec<TrackData>,
+    /// Numbers still pending when polling gave up
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    incomplete: Vec<String>,
+}
+
+/// Track multiple packages, each with its own carrier
+async fn track_batch_v2(
+    State(state): State<AppState>,
+    Json(request): Json<BatchTrackV2Request>,
+) -> Result<(StatusCode, Json<BatchTrackV2Response>), ApiError> {
+    state.metrics.total_requests.fetch_add(1, Ordering::Relaxed);
+    state
+        .metrics
+        .requests_in_flight
+        .fetch_add(1, Ordering::Relaxed);
+    let _guard = RequestGuard(&state.metrics.requests_in_flight);
+    let _timer = state.metrics.request_duration.start_timer();
+
+    if request.items.is_empty() {
+        return Err(ApiError::BadRequest("items cannot be empty".to_string()));
+    }
+
+    let targets = request
+        .items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| item.to_target(i))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    tracing::info!("Batch v2 tracking {} packages", targets.len());
+
+    let mut response = track_cancellable(state.client.clone(), targets.clone())
+        .await
+        .map_err(|e| {
+            tracing::error!("Batch v2 tracking error: {}", e);
+            ApiError::InternalError(e.to_string())
+        })?;
+
+    // Preserve input order; numbers without a shipment are reported individually
+    let data = targets
+        .iter()
+        .map(|target| {
+            let pos = response
+                .shipments
+                .iter()
+                .position(|s| s.number == target.number);
+            match pos.map(|p| response.shipments.remove(p)) {
+                Some(shipment) if shipment.needs_params() => BatchItemResult {
+                    tracking_number: target.number.clone(),
+                    success: false,
+                    data: None,
+                    error: Some(format!(
+                        "Carrier requires additional parameters: {}",
+                        shipment
+                            .required_params()
+                            .iter()
+                            .map(|p| p.key.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                },
+                Some(shipment) => BatchItemResult {
+                    tracking_number: target.number.clone(),
+                    success: true,
+                    data: Some(TrackData::from_shipment(
+                        &shipment,
+                        request.resolve_locations,
+                    )),
+                    error: None,
+                },
+                None if response.incomplete.contains(&target.number) => BatchItemResult {
+                    tracking_number: target.number.clone(),
+                    success: false,
+                    data: None,
+                    error: Some("Tracking still pending for this package".to_string()),
+                },
+                None => BatchItemResult {
+                    tracking_number: target.number.clone(),
+                    success: false,
+                    data: None,
+                    error: Some("No tracking data found for this package".to_string()),
+                },
+            }
+        })
+        .collect();
+
+    Ok((
+        partial_status(&response.incomplete),
+        Json(BatchTrackV2Response {
+            success: true,
+            data,
+        }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct BatchTrackV2Request {
+    items: Vec<BatchTrackItem>,
+    #[serde(default = "default_resolve_locations")]
+    resolve_locations: bool,
+}
+
+#[derive(Deserialize)]
+struct BatchTrackItem {
+    number: String,
+    /// Carrier name (`auto`, `fedex`, `ups`, `usps`, `dhl`)
+    #[serde(default)]
+    carrier: Option<String>,
+    /// Numeric carrier code; takes precedence over `carrier`
+    #[serde(default)]
+    carrier_code: Option<u32>,
+    /// Extra carrier inputs, passed through as-is
+    #[serde(default)]
+    params: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Caller's label for the package, echoed back in its result
+    #[serde(default)]
+    tag: Option<String>,
+}
+
+impl BatchTrackItem {
+    fn to_target(&self, index: usize) -> Result<TrackTarget, ApiError> {
+        if self.number.trim().is_empty() {
+            return Err(ApiError::BadRequest(format!(
+                "items[{}]: number cannot be empty",
+                index
+            )));
+        }
+
+        let carrier_code = match (self.carrier_code, self.carrier.as_deref()) {
+            (Some(code), _) => code,
+            (None, Some(name)) => carriers::carrier_from_name(name).ok_or_else(|| {
+                ApiError::BadRequest(format!("items[{}]: unknown carrier '{}'", index, name))
+            })?,
+            (None, None) => carriers::AUTO,
+        };
+
+        let mut target = TrackTarget::new(self.number.trim(), carrier_code);
+        target.params = self.params.clone();
+        target.tag = self.tag.clone();
+        Ok(target)
+    }
+}
+
+#[derive(Serialize)]
+struct BatchTrackV2Response {
+    success: bool,
+    data: Vec<BatchItemResult>,
+}
+
+#[derive(Serialize)]
+struct BatchItemResult {
+    tracking_number: String,
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<TrackData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Upper bound on numbers polled at once for a single `/api/track/bulk` body
+/// or `/ws/track` connection
+const BULK_CONCURRENCY: usize = 16;
+
+/// Track packages from an NDJSON body, streaming one NDJSON result per package
+///
+/// Each input line is a bare tracking number, a JSON string, or a batch v2
+/// item (`{"number": "...", "carrier": "ups"}`). Numbers are polled as lines
+/// arrive, at most [`BULK_CONCURRENCY`] at a time, and each `TrackData` line is
+/// written as soon as it resolves. Failures and unparseable lines are written
+/// as `{"tracking_number", "success": false, "error"}`.
+async fn track_bulk(State(state): State<AppState>, body: Body) -> Response {
+    state.metrics.total_requests.fetch_add(1, Ordering::Relaxed);
+
+    // Invalid lines bypass polling. The sender is part of the target stream's
+    // state, not a closure the polling buffer keeps alive, so it is dropped
+    // (closing the channel) as soon as the body is exhausted.
+    let (invalid_tx, invalid_rx) = tokio::sync::mpsc::unbounded_channel();
+    let lines = ndjson_lines(body)
+        .enumerate()
+        .filter(|(_, line)| futures::future::ready(!line.is_empty()))
+        .boxed();
+    let targets =
+        futures::stream::unfold((lines, invalid_tx), |(mut lines, invalid_tx)| async move {
+            while let Some((i, line)) = lines.next().await {
+                match parse_bulk_line(i, &line) {
+                    Ok(target) => return Some((target, (lines, invalid_tx))),
+                    Err(error) => {
+                        let _ = invalid_tx.send(BatchItemResult {
+                            tracking_number: line,
+                            success: false,
+                            data: None,
+                            error: Some(error),
+                        });
+                    }
+                }
+            }
+            None
+        });
+    let invalid = futures::stream::unfold(invalid_rx, |mut rx| async move {
+        rx.recv().await.map(|invalid| (invalid, rx))
+    })
+    .map(serde_json::to_value);
+
+    let results = state
+        .client
+        .track_stream_limited(targets, BULK_CONCURRENCY)
+        .map(|(target, result)| match result {
+            Ok(shipment) => serde_json::to_value(TrackData::from_shipment(&shipment, true)),
+            Err(e) => {
+                tracing::error!("Bulk tracking error for {}: {}", target.number, e);
+                serde_json::to_value(BatchItemResult {
+                    tracking_number: target.number,
+                    success: false,
+                    data: None,
+                    error: Some(e.to_string()),
+                })
+            }
+        });
+
+    let lines = futures::stream::select(results, invalid).filter_map(|line| {
+        futures::future::ready(
+            line.ok()
+                .map(|line| Ok::<_, std::convert::Infallible>(format!("{}\n", line))),
+        )
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+/// Split a request body into trimmed lines as chunks arrive
+fn ndjson_lines(body: Body) -> impl futures::Stream<Item = String> + Send + 'static {
+    let state = (body.into_data_stream(), Vec::new(), false);
+    futures::stream::unfold(state, |(mut data, mut buf, mut done)| async move {
+        loop {
+            if let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line).trim().to_string();
+                return Some((line, (data, buf, done)));
+            }
+            if done {
+                if buf.is_empty() {
+                    return None;
+                }
+                let line = String::from_utf8_lossy(&std::mem::take(&mut buf))
+                    .trim()
+                    .to_string();
+                return Some((line, (data, buf, done)));
+            }
+            match data.next().await {
+                Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    tracing::error!("Bulk body read error: {}", e);
+                    done = true;
+                }
+                None => done = true,
+            }
+        }
+    })
+}
+
+/// Parse one `/api/track/bulk` line: a bare number, a JSON string, or a batch v2 item
+fn parse_bulk_line(index: usize, line: &str) -> Result<TrackTarget, String> {
+    let item = if line.starts_with('{') {
+        serde_json::from_str::<BatchTrackItem>(line)
+            .map_err(|e| format!("line {}: invalid item: {}", index + 1, e))?
+    } else {
+        let number = if line.starts_with('"') {
+            serde_json::from_str::<String>(line)
+                .map_err(|e| format!("line {}: invalid string: {}", index + 1, e))?
+        } else {
+            line.to_string()
+        };
+        BatchTrackItem {
+            number,
+            carrier_code: None,
+            carrier: None,
+            params: None,
+            tag: None,
+        }
+    };
+
+    // Reword `items[i]: ...` errors in terms of input lines
+    item.to_target(index).map_err(|e| {
+        let reason = match &e {
+            ApiError::BadRequest(msg) => msg.split_once(": ").map_or(msg.as_str(), |(_, r)| r),
+            _ => "invalid item",
+        };
+        format!("line {}: {}", index + 1, reason)
+    })
+}
+
+/// Stream tracking updates over a WebSocket
+///
+/// Clients send `{"subscribe": ["NUM1", "NUM2"], "carrier": "auto"}` frames.
+/// Each number is polled independently and a `TrackData` frame is sent as soon
+/// as it resolves; failures are sent as `{"tracking_number", "success": false,
+/// "error"}`. Once every subscription has resolved the server sends
+/// `{"done": true}`. Closing the socket cancels all in-flight polls.
+async fn ws_track(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(move |socket| handle_ws_track(socket, state))
+}
+
+#[derive(Deserialize)]
+struct WsSubscribeRequest {
+    subscribe: Vec<String>,
+    /// Carrier name (`auto`, `fedex`, `ups`, `usps`, `dhl`)
+    #[serde(default)]
+    carrier: Option<String>,
+}
+
+impl WsSubscribeRequest {
+    fn to_targets(&self) -> Result<Vec<TrackTarget>, String> {
+        let carrier_code = match self.carrier.as_deref() {
+            Some(name) => carriers::carrier_from_name(name)
+                .ok_or_else(|| format!("unknown carrier '{}'", name))?,
+            None => carriers::AUTO,
+        };
+
+        Ok(self
+            .subscribe
+            .iter()
+            .map(|num| num.trim())
+            .filter(|num| !num.is_empty())
+            .map(|num| TrackTarget::new(num, carrier_code))
+            .collect())
+    }
+}
+
+async fn handle_ws_track(mut socket: WebSocket, state: AppState) {
+    state
+        .metrics
+        .requests_in_flight
+        .fetch_add(1, Ordering::Relaxed);
+    let _guard = RequestGuard(&state.metrics.requests_in_flight);
+
+    // Cancel every poll when the connection goes away
+    let cancel = CancellationToken::new();
+    let _cancel_on_drop = cancel.clone().drop_guard();
+    let mut polls = JoinSet::new();
+    // Subscriptions queue for a permit, so a huge frame can't start every poll at once
+    let permits = Arc::new(Semaphore::new(BULK_CONCURRENCY));
+
+    loop {
+        let frame = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    state.metrics.total_requests.fetch_add(1, Ordering::Relaxed);
+                    let targets = serde_json::from_str::<WsSubscribeRequest>(&text)
+                        .map_err(|e| format!("invalid subscribe frame: {}", e))
+                        .and_then(|request| request.to_targets());
+                    match targets {
+                        Ok(targets) if !targets.is_empty() => {
+                            tracing::info!("WebSocket subscribed to {} packages", targets.len());
+                            for target in targets {
+                                let client = state.client.clone();
+                                let cancel = cancel.clone();
+                                let permits = permits.clone();
+                                polls.spawn(async move {
+                                    let _permit = permits.acquire_owned().await;
+                                    let result = client
+                                        .track_targets_with_cancel(std::slice::from_ref(&target), &cancel)
+                                        .await;
+                                    (target.number, result)
+                                });
+                            }
+                            continue;
+                        }
+                        Ok(_) => serde_json::json!({"error": "subscribe cannot be empty"}),
+                        Err(e) => serde_json::json!({"error": e}),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            Some(joined) = polls.join_next() => {
+                let Ok((number, result)) = joined else {
+                    tracing::error!("WebSocket tracking task panicked");
+                    continue;
+                };
+                let Ok(frame) = ws_result_frame(number, result) else { continue };
+                if socket.send(Message::Text(frame.to_string())).await.is_err() {
+                    break;
+                }
+                if !polls.is_empty() {
+                    continue;
+                }
+                serde_json::json!({"done": true})
+            }
+        };
+
+        if socket.send(Message::Text(frame.to_string())).await.is_err() {
+            break;
+        }
+    }
+
+    tracing::info!(
+        "WebSocket closed, cancelling {} in-flight polls",
+        polls.len()
+    );
+}
+
+/// The frame reporting one subscribed package's result
+fn ws_result_frame(
+    number: String,
+    result: anyhow::Result<TrackingResponse>,
+) -> serde_json::Result<serde_json::Value> {
+    let failure = |error: String| BatchItemResult {
+        tracking_number: number.clone(),
+        success: false,
+        data: None,
+        error: Some(error),
+    };
+    match result {
+        Ok(response) => match response.shipments.first() {
+            Some(shipment) => serde_json::to_value(TrackData::from_shipment(shipment, true)),
+            None if response.incomplete.contains(&number) => serde_json::to_value(failure(
+                "Tracking still pending for this package".to_string(),
+            )),
+            None => serde_json::to_value(failure(
+                "No tracking data found for this package".to_string(),
+            )),
+        },
+        Err(e) => {
+            tracing::error!("WebSocket tracking error: {}", e);
+            serde_json::to_value(failure(e.to_string()))
+        }
+    }
+}
+
+/// Server metrics in Prometheus text exposition format
+async fn get_prometheus_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let metrics = &state.metrics;
+    let mut out = String::new();
+
+    for (name, kind, help, value) in [
+        (
+            "track17_requests_total",
+            "counter",
+            "Total tracking requests received.",
+            metrics.total_requests.load(Ordering::Relaxed),
+        ),
+        (
+            "track17_requests_in_flight",
+            "gauge",
+            "Tracking requests currently being processed.",
+            metrics.requests_in_flight.load(Ordering::Relaxed),
+        ),
+        (
+            "track17_credential_refreshes_total",
+            "counter",
+            "Credential refreshes forced by the API rejecting credentials.",
+            metrics.credential_refreshes.load(Ordering::Relaxed),
+        ),
+    ] {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+    metrics.request_duration.render(
+        "track17_request_duration_seconds",
+        "Tracking request duration in seconds.",
+        &mut out,
+    );
+
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        out,
+    )
 }
 
 /// Get server metrics
@@ -281,70 +1579,183 @@ struct TrackData {
     tracking_number: String,
     carrier: u32,
     status: String,
+    /// Broad bucket of `status` (pending, in transit, delivered, exception, unknown)
+    category: String,
+    progress: u8,
     latest_event: Option<EventData>,
     all_events: Vec<EventData>,
+    milestones: Vec<MilestoneData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    origin_country: Option<CountryData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    destination_country: Option<CountryData>,
+    /// Carrier's own tracking page (17track's for unrecognized carriers)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    carrier_url: Option<String>,
+    /// Status change 17track reports as recent, for notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transition: Option<TransitionData>,
+    /// Newest event's fingerprint as 16 hex digits; unchanged means no new events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event_fingerprint: Option<String>,
+    /// Carriers to choose from when auto-detection was ambiguous and
+    /// `resolve_carrier` was false
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    candidate_carriers: Vec<CarrierOption>,
+    /// Label the caller sent with the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CarrierOption {
+    carrier_code: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    carrier_name: Option<&'static str>,
+}
+
+impl CarrierOption {
+    /// Candidates 17track offered for an ambiguous (code 400) shipment
+    fn candidates(shipment: &Shipment) -> Vec<Self> {
+        if shipment.code != 400 {
+            return Vec::new();
+        }
+        shipment
+            .carrier_suggestions()
+            .map(|code| Self {
+                carrier_code: code,
+                carrier_name: carriers::carrier_name(code),
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct TransitionData {
+    from: String,
+    to: String,
+}
+
+#[derive(Serialize)]
+struct CountryData {
+    code: String,
+    name: Option<String>,
+}
+
+impl CountryData {
+    fn new(code: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            name: country_name(code).map(str::to_string),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MilestoneData {
+    status: String,
+    time: Option<String>,
 }
 
 #[derive(Serialize)]
 struct EventData {
     time: String,
     description: String,
+    /// Carrier's untranslated text, when a translation was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description_original: Option<String>,
+    /// Location with postal codes resolved to "City, ST" when possible
     location: Option<String>,
+    /// Location as reported by the carrier (e.g., "US 60455")
+    raw_location: Option<String>,
 }
 
 impl TrackData {
-    fn from_shipment(shipment: &Shipment) -> Self {
+    fn from_shipment(shipment: &Shipment, resolve_locations: bool) -> Self {
         let latest_event = shipment
             .shipment
             .as_ref()
             .and_then(|s| s.latest_event.as_ref())
-            .map(EventData::from_tracking_event);
+            .map(|e| EventData::from_tracking_event(e, resolve_locations));
 
+        // Every provider's events (origin and destination carrier), newest first
         let all_events = shipment
             .shipment
             .as_ref()
-            .and_then(|s| s.tracking.as_ref())
-            .and_then(|t| t.providers.as_ref())
-            .and_then(|p| p.first())
-            .map(|provider| {
-                provider
-                    .events
+            .map(|s| {
+                s.events()
+                    .into_iter()
+                    .map(|e| EventData::from_tracking_event(e, resolve_locations))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let milestones = shipment
+            .shipment
+            .as_ref()
+            .map(|s| {
+                s.milestones()
                     .iter()
-                    .map(EventData::from_tracking_event)
+                    .map(|m| MilestoneData {
+                        status: m.state.to_string(),
+                        time: m.time.clone(),
+                    })
                     .collect()
             })
             .unwrap_or_default();
 
+        let state = shipment.tracking_state();
+
         Self {
             tracking_number: shipment.number.clone(),
-            carrier: shipment.carrier,
-            status: shipment
-                .shipment
-                .as_ref()
-                .and_then(|s| s.latest_event.as_ref())
-                .map(|e| e.tracking_state().to_string())
-                .unwrap_or_else(|| "UNKNOWN".to_string()),
+            carrier: shipment.resolved_carrier(),
+            status: state.to_string(),
+            category: state.category().to_string(),
+            progress: state.progress(),
             latest_event,
             all_events,
+            milestones,
+            origin_country: shipment.origin_country().map(CountryData::new),
+            destination_country: shipment.destination_country().map(CountryData::new),
+            carrier_url: carriers::tracking_url(shipment.resolved_carrier(), &shipment.number),
+            transition: shipment
+                .just_transitioned()
+                .map(|(from, to)| TransitionData {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                }),
+            event_fingerprint: shipment
+                .shipment
+                .as_ref()
+                .and_then(|s| s.latest_event_fingerprint())
+                .map(|fingerprint| format!("{:016x}", fingerprint)),
+            candidate_carriers: CarrierOption::candidates(shipment),
+            tag: shipment.tag.clone(),
         }
     }
 }
 
 impl EventData {
-    fn from_tracking_event(event: &TrackingEvent) -> Self {
+    fn from_tracking_event(event: &TrackingEvent, resolve_locations: bool) -> Self {
+        let raw_location = event.raw_location();
+        let location = match &raw_location {
+            Some(raw) if resolve_locations => Some(format_location(raw)),
+            _ => raw_location.clone(),
+        };
+
         Self {
             time: event
-                .time_iso
-                .clone()
+                .timestamp_rfc3339()
+                .or_else(|| event.time_iso.clone())
                 .or_else(|| event.time.clone())
                 .unwrap_or_else(|| "N/A".to_string()),
             description: event
                 .description
                 .clone()
                 .unwrap_or_else(|| "N/A".to_string()),
-            location: event
-                .raw_location()
-                .map(|loc| format_location(loc.as_str())),
+            description_original: event.description_original.clone(),
+            location,
+            raw_location,
         }
     }
 }
@@ -353,14 +1764,40 @@ impl EventData {
 enum ApiError {
     BadRequest(String),
     NotFound(String),
+    /// The carrier needs extra input (e.g., a postal code) before tracking
+    MissingParams(Vec<ParamV2>),
+    /// Auto-detection matched several carriers; the caller must pick one
+    AmbiguousCarrier(Vec<CarrierOption>),
+    /// The `Idempotency-Key` was first used with a different request
+    IdempotencyKeyReused,
     InternalError(String),
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, message) = match self {
+            ApiError::MissingParams(params) => {
+                let body = Json(serde_json::json!({
+                    "success": false,
+                    "error": "Carrier requires additional parameters",
+                    "required_params": params,
+                }));
+                return (StatusCode::UNPROCESSABLE_ENTITY, body).into_response();
+            }
+            ApiError::AmbiguousCarrier(candidates) => {
+                let body = Json(serde_json::json!({
+                    "success": false,
+                    "error": "Carrier is ambiguous; retry with one of candidate_carriers",
+                    "candidate_carriers": candidates,
+                }));
+                return (StatusCode::MULTIPLE_CHOICES, body).into_response();
+            }
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            ApiError::IdempotencyKeyReused => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used with a different request".to_string(),
+            ),
             ApiError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
         };
 
@@ -403,3 +1840,789 @@ async fn shutdown_signal() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+    use track17_rs::testing::{MockTransport, ResponseBuilder, ShipmentBuilder};
+
+    fn delivered_shipment(number: &str, carrier: u32) -> Value {
+        ShipmentBuilder::new(number)
+            .carrier(carrier)
+            .delivered_event("2024-01-15T10:30:00Z")
+            .to_json()
+    }
+
+    async fn post_json(app: Router, uri: &str, body: Value) -> (StatusCode, Value) {
+        let response = app
+            .oneshot(
+                Request::post(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_delivered_without_events() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push_response(
+            ResponseBuilder::new("abc")
+                .shipment(
+                    ShipmentBuilder::new("NUM1")
+                        .carrier(carriers::UPS)
+                        .state_final("Delivered"),
+                )
+                .build(),
+        );
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let (status, body) = post_json(app, "/api/track", json!({"tracking_number": "NUM1"})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "DELIVERED");
+        assert_eq!(body["data"]["category"], "DELIVERED");
+        assert_eq!(body["data"]["progress"], 100);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_v2_mixed_carriers() {
+        let transport = Arc::new(MockTransport::new());
+        let mut ups = delivered_shipment("UPS1", carriers::UPS);
+        ups["tag"] = json!("order-7");
+        // Shipments come back out of order; the response must follow input order
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [delivered_shipment("USPS1", carriers::USPS), ups],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let (status, body) = post_json(
+            app,
+            "/api/track/batch/v2",
+            json!({"items": [
+                {"number": "UPS1", "carrier": "ups", "tag": "order-7"},
+                {"number": "USPS1", "carrier_code": 100002}
+            ]}),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"][0]["tracking_number"], "UPS1");
+        assert_eq!(body["data"][0]["data"]["status"], "DELIVERED");
+        assert_eq!(body["data"][0]["data"]["tag"], "order-7");
+        assert_eq!(body["data"][1]["tracking_number"], "USPS1");
+        assert!(body["data"][1]["data"].get("tag").is_none());
+
+        let items = &transport.requests()[0].items;
+        assert_eq!(items[0].fc, carriers::UPS);
+        assert_eq!(items[0].tag.as_deref(), Some("order-7"));
+        assert_eq!(items[1].fc, carriers::USPS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_reports_incomplete_numbers() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [delivered_shipment("NUM1", carriers::UPS)],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        // NUM2 never resolves
+        for _ in 0..10 {
+            transport.push_json(json!({
+                "id": 1,
+                "guid": "abc",
+                "shipments": [],
+                "meta": {"code": 200, "message": "Ok"}
+            }));
+        }
+        let app = build_app(Track17Client::with_transport(transport));
+
+        let (status, body) = post_json(
+            app,
+            "/api/track/batch",
+            json!({"tracking_numbers": ["NUM1", "NUM2"]}),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["data"][0]["tracking_number"], "NUM1");
+        assert_eq!(body["incomplete"], json!(["NUM2"]));
+    }
+
+    #[tokio::test]
+    async fn test_delete_cached_number() {
+        let transport = Arc::new(MockTransport::new());
+        for _ in 0..2 {
+            transport.push_json(json!({
+                "id": 1,
+                "guid": "abc",
+                "shipments": [delivered_shipment("NUM1", carriers::UPS)],
+                "meta": {"code": 200, "message": "Ok"}
+            }));
+        }
+        let client = Track17Client::with_transport_and_config(
+            transport.clone(),
+            Track17Config {
+                result_cache_ttl: Some(Duration::from_secs(60)),
+                ..Default::default()
+            },
+        );
+        let app = build_app(client);
+
+        for _ in 0..2 {
+            let (status, _) = post_json(
+                app.clone(),
+                "/api/track",
+                json!({"tracking_number": "NUM1", "carrier_code": carriers::UPS}),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        assert_eq!(transport.requests().len(), 1);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::delete("/api/track/cache/NUM1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        post_json(
+            app,
+            "/api/track",
+            json!({"tracking_number": "NUM1", "carrier_code": carriers::UPS}),
+        )
+        .await;
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_status() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [{
+                "code": 200,
+                "number": "NUM1",
+                "carrier": carriers::UPS,
+                "prior_status": "OutForDelivery",
+                "shipment": {
+                    "latest_event": {
+                        "time_iso": "2024-01-15T10:30:00Z",
+                        "description": "Delivered, signed by J SMITH",
+                        "stage": "Delivered",
+                        "sub_status": "Delivered_Signed"
+                    }
+                }
+            }],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        let app = build_app(Track17Client::with_transport(transport));
+
+        let (status, body) = post_json(app, "/api/track", json!({"tracking_number": "NUM1"})).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["status"], "DELIVERED_SIGNED");
+        assert_eq!(body["data"]["category"], "DELIVERED");
+        assert_eq!(
+            body["data"]["transition"],
+            json!({"from": "OUT_FOR_DELIVERY", "to": "DELIVERED_SIGNED"})
+        );
+        assert_eq!(
+            body["data"]["event_fingerprint"].as_str().map(str::len),
+            Some(16)
+        );
+        assert_eq!(
+            body["data"]["carrier_url"],
+            "https://www.ups.com/track?tracknum=NUM1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_openapi_lists_paths() {
+        let app = build_app(Track17Client::with_transport(
+            Arc::new(MockTransport::new()),
+        ));
+        let response = app
+            .oneshot(Request::get("/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let spec: Value = serde_json::from_slice(&bytes).unwrap();
+
+        for path in [
+            "/health",
+            "/ready",
+            "/api/track",
+            "/api/track/batch",
+            "/api/track/batch/v2",
+            "/api/track/bulk",
+            "/api/track/cache/{number}",
+            "/metrics",
+            "/ws/track",
+        ] {
+            assert!(spec["paths"].get(path).is_some(), "missing {}", path);
+        }
+        let statuses = spec["components"]["schemas"]["TrackingState"]["enum"]
+            .as_array()
+            .unwrap();
+        assert!(statuses.contains(&json!("DELIVERED")));
+        assert_eq!(statuses.len(), TrackingState::ALL.len());
+    }
+
+    #[tokio::test]
+    async fn test_readiness_reports_failing_component() {
+        let get_ready = |app: Router| async move {
+            let response = app
+                .oneshot(Request::get("/ready").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let status = response.status();
+            let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            (status, serde_json::from_slice::<Value>(&bytes).unwrap())
+        };
+
+        let transport = Arc::new(MockTransport::new());
+        let (status, body) =
+            get_ready(build_app(Track17Client::with_transport(transport.clone()))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["cdn"]["status"], "ok");
+        assert_eq!(body["v8"]["status"], "ok");
+        assert_eq!(body["proxy"]["status"], "skipped");
+
+        transport.fail_warm_up(TrackError::AssetFetch {
+            stage: track17_rs::AssetStage::TrackingPage,
+            attempts: 3,
+        });
+        let (status, body) = get_ready(build_app(Track17Client::with_transport(transport))).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["cdn"]["status"], "error");
+        assert!(
+            body["cdn"]["error"]
+                .as_str()
+                .unwrap()
+                .contains("tracking page")
+        );
+        // Never reached without assets
+        assert_eq!(body["v8"]["status"], "skipped");
+        assert_eq!(body["proxy"]["status"], "skipped");
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_coalesces_requests() {
+        let transport = Arc::new(MockTransport::with_latency(Duration::from_millis(50)));
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [delivered_shipment("NUM1", carriers::UPS)],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let send = || {
+            app.clone().oneshot(
+                Request::post("/api/track")
+                    .header("content-type", "application/json")
+                    .header("idempotency-key", "retry-1")
+                    .body(Body::from(json!({"tracking_number": "NUM1"}).to_string()))
+                    .unwrap(),
+            )
+        };
+        let (first, retry) = tokio::join!(send(), send());
+
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        assert_eq!(retry.unwrap().status(), StatusCode::OK);
+        assert_eq!(transport.requests().len(), 1);
+
+        // Within the window a later retry gets the stored result
+        assert_eq!(send().await.unwrap().status(), StatusCode::OK);
+        assert_eq!(transport.requests().len(), 1);
+
+        // The same key for another package is refused, not answered with NUM1
+        let reused = app
+            .clone()
+            .oneshot(
+                Request::post("/api/track")
+                    .header("content-type", "application/json")
+                    .header("idempotency-key", "retry-1")
+                    .body(Body::from(json!({"tracking_number": "NUM2"}).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_retries_after_error() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push_error("connection reset");
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [delivered_shipment("NUM1", carriers::UPS)],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let send = || {
+            app.clone().oneshot(
+                Request::post("/api/track")
+                    .header("content-type", "application/json")
+                    .header("idempotency-key", "retry-1")
+                    .body(Body::from(json!({"tracking_number": "NUM1"}).to_string()))
+                    .unwrap(),
+            )
+        };
+        assert_eq!(
+            send().await.unwrap().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        // The failure isn't replayed; the retry polls again
+        assert_eq!(send().await.unwrap().status(), StatusCode::OK);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_parse_allowed_origins() {
+        let origins =
+            parse_allowed_origins("https://app.example.com, http://localhost:8080").unwrap();
+        assert_eq!(
+            origins,
+            ["https://app.example.com", "http://localhost:8080"]
+        );
+
+        assert!(parse_allowed_origins("app.example.com").is_err());
+        assert!(parse_allowed_origins("https://app.example.com/path").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cors_rejects_disallowed_origin() {
+        let origins = parse_allowed_origins("https://app.example.com").unwrap();
+        let app = build_app(Track17Client::with_transport(
+            Arc::new(MockTransport::new()),
+        ))
+        .layer(cors_layer(Some(origins)));
+
+        let allow_origin = |origin: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::get("/health")
+                            .header(header::ORIGIN, origin)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                response
+                    .headers()
+                    .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                    .cloned()
+            }
+        };
+
+        assert_eq!(
+            allow_origin("https://app.example.com").await.unwrap(),
+            "https://app.example.com"
+        );
+        assert!(allow_origin("https://evil.example.com").await.is_none());
+
+        // Browsers preflight the Idempotency-Key header before a retryable POST
+        let preflight = app
+            .clone()
+            .oneshot(
+                Request::options("/api/track")
+                    .header(header::ORIGIN, "https://app.example.com")
+                    .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                    .header(
+                        header::ACCESS_CONTROL_REQUEST_HEADERS,
+                        "content-type, idempotency-key",
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let allowed = preflight.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap()
+            .to_lowercase();
+        assert!(allowed.contains("idempotency-key"));
+    }
+
+    #[tokio::test]
+    async fn test_ws_track_streams_updates() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+        let transport = Arc::new(MockTransport::new());
+        // Each number is polled on its own; extra shipments in a response are ignored
+        for _ in 0..2 {
+            transport.push_json(json!({
+                "id": 1,
+                "guid": "abc",
+                "shipments": [
+                    delivered_shipment("NUM1", carriers::UPS),
+                    delivered_shipment("NUM2", carriers::USPS),
+                ],
+                "meta": {"code": 200, "message": "Ok"}
+            }));
+        }
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws/track", addr))
+            .await
+            .unwrap();
+        ws.send(WsMessage::Text(
+            json!({"subscribe": ["NUM1", "NUM2"], "carrier": "auto"}).to_string(),
+        ))
+        .await
+        .unwrap();
+
+        let mut numbers = Vec::new();
+        loop {
+            let frame = match ws.next().await.unwrap().unwrap() {
+                WsMessage::Text(text) => serde_json::from_str::<Value>(&text).unwrap(),
+                _ => continue,
+            };
+            if frame["done"] == true {
+                break;
+            }
+            assert_eq!(frame["status"], "DELIVERED");
+            numbers.push(frame["tracking_number"].as_str().unwrap().to_string());
+        }
+
+        numbers.sort();
+        assert_eq!(numbers, vec!["NUM1", "NUM2"]);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_ws_result_frame_reports_pending() {
+        let mut pending = ResponseBuilder::new("abc").build();
+        pending.incomplete = vec!["NUM1".to_string()];
+        let frame = ws_result_frame("NUM1".to_string(), Ok(pending)).unwrap();
+        assert_eq!(frame["success"], false);
+        assert_eq!(frame["error"], "Tracking still pending for this package");
+
+        let missing = ResponseBuilder::new("abc").build();
+        let frame = ws_result_frame("NUM1".to_string(), Ok(missing)).unwrap();
+        assert_eq!(frame["error"], "No tracking data found for this package");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_ndjson_streams_results() {
+        let transport = Arc::new(MockTransport::new());
+        for _ in 0..3 {
+            transport.push_json(json!({
+                "id": 1,
+                "guid": "abc",
+                "shipments": [
+                    delivered_shipment("NUM1", carriers::UPS),
+                    delivered_shipment("NUM2", carriers::USPS),
+                    delivered_shipment("NUM3", carriers::FEDEX),
+                ],
+                "meta": {"code": 200, "message": "Ok"}
+            }));
+        }
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let body =
+            "NUM1\n\"NUM2\"\n\n{\"number\": \"NUM3\", \"carrier\": \"fedex\"}\n{\"number\": \"\"}";
+        let response = app
+            .oneshot(
+                Request::post("/api/track/bulk")
+                    .header("content-type", "application/x-ndjson")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let lines: Vec<Value> = std::str::from_utf8(&bytes)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+
+        let mut numbers: Vec<_> = lines
+            .iter()
+            .filter(|line| line["status"] == "DELIVERED")
+            .map(|line| line["tracking_number"].as_str().unwrap())
+            .collect();
+        numbers.sort();
+        assert_eq!(numbers, vec!["NUM1", "NUM2", "NUM3"]);
+        assert!(
+            lines.iter().any(|line| line["success"] == false
+                && line["error"] == "line 5: number cannot be empty")
+        );
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_body_ends_after_last_line() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [delivered_shipment("NUM1", carriers::UPS)],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        let app = build_app(Track17Client::with_transport(transport));
+
+        for body in ["NUM1\n{\"number\": \"\"}\n", "{\"number\": \"\"}"] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::post("/api/track/bulk")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            // Reading to EOF must not wait on a still-open invalid-line channel
+            let bytes = tokio::time::timeout(
+                Duration::from_secs(5),
+                to_bytes(response.into_body(), usize::MAX),
+            )
+            .await
+            .expect("bulk response body never ended")
+            .unwrap();
+            assert!(!bytes.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push_json(json!({
+            "id": 0,
+            "guid": "",
+            "shipments": [],
+            "meta": {"code": -11, "message": "Invalid sign"}
+        }));
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [delivered_shipment("NUM1", carriers::UPS)],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let (status, _) = post_json(
+            app.clone(),
+            "/api/track",
+            json!({"tracking_number": "NUM1"}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let response = app
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(bytes.to_vec()).unwrap();
+
+        let value = |name: &str| -> f64 {
+            body.lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+                .unwrap_or_else(|| panic!("missing metric {}", name))
+                .parse()
+                .unwrap()
+        };
+        assert_eq!(value("track17_requests_total"), 1.0);
+        assert_eq!(value("track17_requests_in_flight"), 0.0);
+        assert_eq!(value("track17_credential_refreshes_total"), 1.0);
+        assert_eq!(value("track17_request_duration_seconds_count"), 1.0);
+        assert_eq!(
+            value("track17_request_duration_seconds_bucket{le=\"+Inf\"}"),
+            1.0
+        );
+        assert!(value("track17_request_duration_seconds_sum") >= 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_event_location_raw_and_resolved() {
+        let shipment = json!({
+            "code": 200,
+            "number": "NUM1",
+            "carrier": carriers::USPS,
+            "shipment": {
+                "tracking": null,
+                "latest_event": {
+                    "time_iso": "2024-01-15T10:30:00Z",
+                    "description": "Arrived at facility",
+                    "stage": "InTransit",
+                    "location": "US 60455"
+                }
+            }
+        });
+        let transport = Arc::new(MockTransport::new());
+        for _ in 0..2 {
+            transport.push_json(json!({
+                "id": 1,
+                "guid": "abc",
+                "shipments": [shipment.clone()],
+                "meta": {"code": 200, "message": "Ok"}
+            }));
+        }
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let (_, body) = post_json(
+            app.clone(),
+            "/api/track",
+            json!({"tracking_number": "NUM1"}),
+        )
+        .await;
+        let event = &body["data"]["latest_event"];
+        assert_eq!(event["raw_location"], "US 60455");
+        assert_ne!(event["location"], event["raw_location"]);
+
+        let (_, body) = post_json(
+            app,
+            "/api/track",
+            json!({"tracking_number": "NUM1", "resolve_locations": false}),
+        )
+        .await;
+        let event = &body["data"]["latest_event"];
+        assert_eq!(event["location"], "US 60455");
+        assert_eq!(event["raw_location"], "US 60455");
+    }
+
+    #[tokio::test]
+    async fn test_unresolved_carrier_returns_candidates() {
+        let ambiguous = json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [{
+                "code": 400,
+                "number": "NUM1",
+                "carrier": 0,
+                "extra": [{"multi": [carriers::FEDEX, carriers::UPS]}]
+            }],
+            "meta": {"code": 200, "message": "Ok"}
+        });
+        let transport = Arc::new(MockTransport::new());
+        transport.push_json(ambiguous.clone());
+        transport.push_json(ambiguous);
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let (status, body) = post_json(
+            app.clone(),
+            "/api/track",
+            json!({"tracking_number": "NUM1", "resolve_carrier": false}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::MULTIPLE_CHOICES);
+        assert_eq!(body["success"], false);
+        assert_eq!(
+            body["candidate_carriers"],
+            json!([
+                {"carrier_code": carriers::FEDEX, "carrier_name": "FedEx"},
+                {"carrier_code": carriers::UPS, "carrier_name": "UPS"},
+            ])
+        );
+        assert_eq!(transport.requests().len(), 1);
+
+        let (status, body) = post_json(
+            app,
+            "/api/track/batch",
+            json!({"tracking_numbers": ["NUM1"], "resolve_carrier": false}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body["data"][0]["candidate_carriers"][1]["carrier_code"],
+            carriers::UPS
+        );
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_track_missing_params_returns_422() {
+        let transport = Arc::new(MockTransport::new());
+        transport.push_json(json!({
+            "id": 1,
+            "guid": "abc",
+            "shipments": [{
+                "code": 200,
+                "number": "NUM1",
+                "carrier": 190271,
+                "params_v2": [{
+                    "key": "postal_code",
+                    "input_type": "text",
+                    "example": "60455",
+                    "regex": "^[0-9]{5}$",
+                    "options": []
+                }],
+                "shipment": null
+            }],
+            "meta": {"code": 200, "message": "Ok"}
+        }));
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let (status, body) = post_json(app, "/api/track", json!({"tracking_number": "NUM1"})).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["required_params"][0]["key"], "postal_code");
+        assert_eq!(body["required_params"][0]["example"], "60455");
+        // Blocked on input: no pointless polling
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batch_v2_invalid_carrier() {
+        let transport = Arc::new(MockTransport::new());
+        let app = build_app(Track17Client::with_transport(transport.clone()));
+
+        let (status, body) = post_json(
+            app,
+            "/api/track/batch/v2",
+            json!({"items": [{"number": "NUM1", "carrier": "pigeon"}]}),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["success"], false);
+        assert!(transport.requests().is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! Blocking wrapper around [`Track17Client`] for callers without a Tokio runtime.
+//!
+//! [`BlockingClient`] owns a current-thread runtime and drives every call to
+//! completion on it. Do not use it from within an async context: building or
+//! blocking on a runtime from inside another one panics. Async code should use
+//! [`Track17Client`] directly.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use track17_rs::blocking::BlockingClient;
+//! use track17_rs::carriers;
+//!
+//! # fn run() -> anyhow::Result<()> {
+//! let client = BlockingClient::new()?;
+//! let response = client.track("1234567890", carriers::AUTO)?;
+//! println!("{}", response.meta.message);
+//! # Ok(())
+//! # }
+//! ```
+
+use anyhow::Result;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::client::{Track17Client, Track17Config};
+use crate::types::TrackingResponse;
+
+/// Synchronous counterpart of [`Track17Client`]
+pub struct BlockingClient {
+    inner: Track17Client,
+    runtime: Runtime,
+}
+
+impl BlockingClient {
+    /// Blocking [`Track17Client::new`]
+    pub fn new() -> Result<Self> {
+        Self::with_config(Track17Config::default())
+    }
+
+    /// Blocking [`Track17Client::with_config`]
+    pub fn with_config(config: Track17Config) -> Result<Self> {
+        let runtime = Self::runtime()?;
+        let inner = runtime.block_on(Track17Client::with_config(config))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Wrap an already-built client (e.g., one using a custom transport)
+    pub fn from_client(inner: Track17Client) -> Result<Self> {
+        Ok(Self {
+            inner,
+            runtime: Self::runtime()?,
+        })
+    }
+
+    fn runtime() -> Result<Runtime> {
+        Ok(Builder::new_current_thread().enable_all().build()?)
+    }
+
+    /// Blocking [`Track17Client::track`]
+    pub fn track(&self, tracking_number: &str, carrier_code: u32) -> Result<TrackingResponse> {
+        self.runtime
+            .block_on(self.inner.track(tracking_number, carrier_code))
+    }
+
+    /// Blocking [`Track17Client::track_multiple`]
+    pub fn track_multiple(
+        &self,
+        tracking_numbers: &[String],
+        carrier_code: u32,
+    ) -> Result<TrackingResponse> {
+        self.runtime
+            .block_on(self.inner.track_multiple(tracking_numbers, carrier_code))
+    }
+
+    /// The wrapped async client
+    pub fn inner(&self) -> &Track17Client {
+        &self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::testing::{MockTransport, ResponseBuilder, ShipmentBuilder};
+    use crate::types::carriers;
+
+    #[test]
+    fn test_blocking_track() {
+        let transport = Arc::new(MockTransport::new());
+        let delivered = |number: &str| {
+            ShipmentBuilder::new(number)
+                .carrier(carriers::UPS)
+                .delivered_event("2024-01-15T10:30:00Z")
+        };
+        transport.push_response(
+            ResponseBuilder::new("abc")
+                .shipment(delivered("NUM1"))
+                .build(),
+        );
+        transport.push_response(
+            ResponseBuilder::new("abc")
+                .shipment(delivered("NUM2"))
+                .shipment(delivered("NUM3"))
+                .build(),
+        );
+
+        let client =
+            BlockingClient::from_client(Track17Client::with_transport(transport.clone())).unwrap();
+
+        let result = client.track("NUM1", carriers::UPS).unwrap();
+        assert_eq!(result.shipments[0].number, "NUM1");
+
+        let result = client
+            .track_multiple(&["NUM2".to_string(), "NUM3".to_string()], carriers::UPS)
+            .unwrap();
+        assert_eq!(result.shipments.len(), 2);
+        assert_eq!(transport.requests().len(), 2);
+    }
+}
@@ -0,0 +1,59 @@
+//! Chrome release impersonated across every layer 17track can observe.
+//!
+//! The TLS/HTTP2 fingerprint (`wreq_util::Emulation`) and the browser mocks the
+//! sign module probes (`navigator.userAgent`, `userAgentData.brands`) must report
+//! the same Chrome version, or 17track can flag the mismatch. Both are derived
+//! from a single [`ChromeProfile`].
+
+use wreq_util::Emulation;
+
+/// A Chrome release to impersonate. Defaults to Chrome 143.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChromeProfile {
+    Chrome140,
+    Chrome141,
+    Chrome142,
+    #[default]
+    Chrome143,
+}
+
+impl ChromeProfile {
+    /// Chrome major version (e.g., `143`)
+    pub fn major_version(self) -> u32 {
+        match self {
+            Self::Chrome140 => 140,
+            Self::Chrome141 => 141,
+            Self::Chrome142 => 142,
+            Self::Chrome143 => 143,
+        }
+    }
+
+    /// TLS/HTTP2 fingerprint for the HTTP client
+    pub fn emulation(self) -> Emulation {
+        match self {
+            Self::Chrome140 => Emulation::Chrome140,
+            Self::Chrome141 => Emulation::Chrome141,
+            Self::Chrome142 => Emulation::Chrome142,
+            Self::Chrome143 => Emulation::Chrome143,
+        }
+    }
+
+    /// `navigator.userAgent` reported to the sign module (Windows desktop)
+    pub fn user_agent(self) -> String {
+        format!(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36",
+            self.major_version()
+        )
+    }
+
+    /// Script defining `globalThis.__chromeProfile`, which `browser_mocks.js`
+    /// reads; it must run first.
+    pub(crate) fn mock_globals_script(self) -> String {
+        let profile = serde_json::json!({
+            "majorVersion": self.major_version().to_string(),
+            "fullVersion": format!("{}.0.0.0", self.major_version()),
+            "userAgent": self.user_agent(),
+        });
+        format!("globalThis.__chromeProfile = {};", profile)
+    }
+}
@@ -2,12 +2,55 @@
 //!
 //! Defines the structure for credentials used in 17track API requests.
 
+use std::fmt;
+
+/// Fallback `configs.md5` when the page does not expose one.
+const DEFAULT_CONFIG_VERSION: &str = "1.0.156";
+
+/// The `window.YQ.configs.md5` page version (e.g., `"1.0.156"`).
+///
+/// Despite the name it is a version string, not a digest. The sign and the
+/// Last-Event-ID must be computed against the same version.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConfigVersion(String);
+
+impl ConfigVersion {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for ConfigVersion {
+    fn default() -> Self {
+        Self(DEFAULT_CONFIG_VERSION.to_string())
+    }
+}
+
+impl From<String> for ConfigVersion {
+    fn from(version: String) -> Self {
+        Self(version)
+    }
+}
+
+impl From<&str> for ConfigVersion {
+    fn from(version: &str) -> Self {
+        Self(version.to_string())
+    }
+}
+
+impl fmt::Display for ConfigVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// API credentials extracted/generated for 17track requests.
 #[derive(Debug, Clone)]
 pub struct ApiCredentials {
     pub sign: String,
     pub last_event_id: String,
     pub yq_bid: String,
-    /// The configs.md5 value from the page (needed for Last-Event-ID generation).
-    pub configs_md5: String,
+    /// The configs.md5 version the sign was generated against (needed for
+    /// Last-Event-ID generation).
+    pub configs_md5: ConfigVersion,
 }
@@ -7,16 +7,24 @@
 //! Note: V8 runtime is not cached because it's not Send/Sync (contains Rc/RefCell).
 //! A fresh runtime is created for each credential generation.
 
-use std::sync::Arc;
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::time::Instant;
 
 use anyhow::{Context, Result};
+use deno_core::v8::IsolateHandle;
 use wreq::Client;
 
-use crate::credential::ApiCredentials;
-use crate::js_fetcher::{self, JsAssets};
+use crate::chrome_profile::ChromeProfile;
+use crate::credential::{ApiCredentials, ConfigVersion};
+use crate::error::TrackError;
+use crate::js_fetcher::{self, AssetFetcher, AssetRetry, JsAssets, TrackingPage};
 use crate::js_runtime::SignGenerator;
-use crate::last_event_id::{self, LastEventIdConfig};
+use crate::last_event_id::{self, FingerprintConfig, LastEventIdConfig};
+use crate::sign_source::SignSource;
+use crate::timing::{RefreshTiming, timed, timed_blocking};
 use crate::yq_bid;
 
 /// Thread-safe credential cache shared across all client clones.
@@ -25,8 +33,8 @@ use crate::yq_bid;
 /// - API credentials (sign, yq_bid, configs_md5)
 /// - JS assets fetched from CDN (1-hour TTL)
 ///
-/// The cache uses `Arc<RwLock<>>` to allow multiple concurrent readers (tracking requests)
-/// while ensuring only one writer can regenerate credentials at a time.
+/// The cache uses `Arc<RwLock<>>` to allow multiple concurrent readers (tracking requests),
+/// and a single-flight lock so only one refresh regenerates credentials at a time.
 ///
 /// Note: V8 runtime is not cached because it's not thread-safe (not Send/Sync).
 /// A fresh runtime is created for each credential generation (~400ms overhead).
@@ -55,11 +63,52 @@ use crate::yq_bid;
 #[derive(Clone)]
 pub struct CredentialCache {
     inner: Arc<RwLock<CredentialCacheInner>>,
+    /// Held for the whole of a refresh, so concurrent refreshers wait for the
+    /// first one instead of each running V8
+    refresh_flight: Arc<tokio::sync::Mutex<()>>,
+    /// Locale of the tracking page JS assets are extracted from
+    locale: Arc<str>,
+    /// Origin serving the tracking page (`https://t.17track.net` unless overridden)
+    page_base: Arc<str>,
+    /// Chrome version the V8 browser mocks report
+    chrome_profile: ChromeProfile,
+    /// Canvas output the V8 browser mocks report and the Last-Event-ID hashes
+    fingerprint: FingerprintConfig,
+    /// Client for the tracking page and CDN assets, if not the caller's
+    asset_client: Option<Client>,
+    /// Retry policy for each tracking page / CDN request
+    asset_retry: AssetRetry,
+    /// External sign generator replacing the V8 path, if configured
+    sign_source: Option<Arc<dyn SignSource>>,
+    /// Sign lengths outside this range are logged as suspicious
+    expected_sign_len: RangeInclusive<usize>,
+    /// Longest V8 runtime creation, module initialization and sign generation may take
+    sign_timeout: Duration,
 }
 
+/// Sign lengths a healthy sign module produces. The JS side only rejects
+/// lengths outside `1..=100000`, so a truncated read can still get through.
+pub const EXPECTED_SIGN_LEN: RangeInclusive<usize> = 200..=5000;
+
+/// Default for [`CredentialCache::with_sign_timeout`]; healthy runs take a
+/// few hundred milliseconds
+pub const DEFAULT_SIGN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a timed-out V8 thread gets to unwind after being terminated
+const V8_STOP_GRACE: Duration = Duration::from_secs(5);
+
+/// How long externally generated signs are reused (matches the JS asset TTL)
+const EXTERNAL_SIGN_TTL: Duration = Duration::from_secs(3600);
+
 struct CredentialCacheInner {
     credentials: Option<ApiCredentials>,
+    /// When `credentials` came from an external sign source
+    external_sign_at: Option<Instant>,
+    /// `credentials` were supplied by the caller; used until invalidated
+    supplied: bool,
     cached_assets: Option<JsAssets>,
+    /// Assets dropped by `invalidate`, kept to detect whether the sign module changed
+    stale_assets: Option<JsAssets>,
     yq_bid: String,
 }
 
@@ -69,15 +118,109 @@ impl CredentialCache {
     /// Generates a fresh `_yq_bid` device identifier that will be reused
     /// for all credentials generated from this cache.
     pub fn new() -> Self {
+        Self::with_locale("en")
+    }
+
+    /// Create a credential cache that extracts assets from the tracking page
+    /// for `locale` (e.g., `https://t.17track.net/fr`).
+    pub fn with_locale(locale: &str) -> Self {
         Self {
             inner: Arc::new(RwLock::new(CredentialCacheInner {
                 credentials: None,
+                external_sign_at: None,
+                supplied: false,
                 cached_assets: None,
+                stale_assets: None,
                 yq_bid: yq_bid::generate_yq_bid(),
             })),
+            refresh_flight: Arc::new(tokio::sync::Mutex::new(())),
+            locale: locale.into(),
+            page_base: js_fetcher::TRACKING_PAGE_BASE.into(),
+            chrome_profile: ChromeProfile::default(),
+            fingerprint: FingerprintConfig::default(),
+            asset_client: None,
+            asset_retry: AssetRetry::default(),
+            sign_source: None,
+            expected_sign_len: EXPECTED_SIGN_LEN,
+            sign_timeout: DEFAULT_SIGN_TIMEOUT,
         }
     }
 
+    /// Fetch the tracking page from `base` (a mirror or mock server) instead
+    /// of `https://t.17track.net`.
+    pub fn with_page_base(mut self, base: &str) -> Self {
+        self.page_base = base.into();
+        self
+    }
+
+    /// Report `profile`'s user agent and brands to the sign module. Match it
+    /// to the HTTP client's emulation (see [`ChromeProfile::emulation`]).
+    pub fn with_chrome_profile(mut self, profile: ChromeProfile) -> Self {
+        self.chrome_profile = profile;
+        self
+    }
+
+    /// Report `fingerprint`'s canvas output to the sign module and hash it
+    /// into the Last-Event-ID.
+    pub fn with_fingerprint(mut self, fingerprint: FingerprintConfig) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    /// Fetch the tracking page and CDN assets with `client` instead of the
+    /// client passed to [`refresh_credentials`](Self::refresh_credentials),
+    /// e.g. to skip a metered proxy for static files.
+    pub fn with_asset_client(mut self, client: Client) -> Self {
+        self.asset_client = Some(client);
+        self
+    }
+
+    /// Retry tracking page and CDN requests per `retry` instead of the defaults.
+    pub fn with_asset_retry(mut self, retry: AssetRetry) -> Self {
+        self.asset_retry = retry;
+        self
+    }
+
+    /// Generate signs with `source` instead of fetching JS assets and running V8.
+    pub fn with_sign_source(mut self, source: Arc<dyn SignSource>) -> Self {
+        self.sign_source = Some(source);
+        self
+    }
+
+    /// Warn about signs whose length falls outside `range` instead of
+    /// [`EXPECTED_SIGN_LEN`].
+    pub fn with_expected_sign_len(mut self, range: RangeInclusive<usize>) -> Self {
+        self.expected_sign_len = range;
+        self
+    }
+
+    /// Give up on V8 sign generation after `timeout` with
+    /// [`TrackError::SignTimeout`] instead of [`DEFAULT_SIGN_TIMEOUT`]. The
+    /// runtime is terminated, so a hung module doesn't keep its thread.
+    pub fn with_sign_timeout(mut self, timeout: Duration) -> Self {
+        self.sign_timeout = timeout;
+        self
+    }
+
+    /// Start with `credentials` extracted out-of-band (see [`set_credentials`](Self::set_credentials)).
+    pub fn with_credentials(self, credentials: ApiCredentials) -> Self {
+        self.inner
+            .try_write()
+            .expect("credential cache is not locked while being built")
+            .supply(credentials);
+        self
+    }
+
+    /// Use `credentials` extracted out-of-band (e.g., from a browser) instead
+    /// of generating them.
+    ///
+    /// They are used regardless of asset freshness until [`invalidate`](Self::invalidate)
+    /// is called (the API rejected them); later refreshes take the normal path.
+    /// An empty `yq_bid` keeps the cache's own device identifier.
+    pub async fn set_credentials(&self, credentials: ApiCredentials) {
+        self.inner.write().await.supply(credentials);
+    }
+
     /// Get valid credentials if available (fast path with read lock).
     ///
     /// Returns `Some(credentials)` if credentials are cached and JS assets are still fresh.
@@ -87,81 +230,89 @@ impl CredentialCache {
     /// concurrently without blocking each other.
     pub async fn get_valid_credentials(&self) -> Option<ApiCredentials> {
         let cache = self.inner.read().await;
-
-        // Check if credentials are still valid
-        if let Some(ref creds) = cache.credentials
-            && cache
-                .cached_assets
-                .as_ref()
-                .map(|a| a.is_fresh())
-                .unwrap_or(false)
-        {
-            return Some(creds.clone());
-        }
-
-        None
+        cache.valid_credentials(self.sign_source.is_some()).cloned()
     }
 
-    /// Refresh credentials (slow path with write lock).
+    /// Refresh credentials (slow path).
     ///
     /// This method:
-    /// 1. Acquires a write lock (blocks other readers and writers)
-    /// 2. Double-checks if another thread already regenerated credentials
+    /// 1. Waits for any refresh already in flight
+    /// 2. Double-checks if that refresh already regenerated credentials
     /// 3. Fetches or reuses cached JS assets (1-hour TTL)
     /// 4. Creates a fresh V8 runtime (~400ms initialization)
     /// 5. Generates fresh credentials
     ///
-    /// The double-check pattern prevents thundering herd: if multiple threads
-    /// detect expired credentials simultaneously, only the first one regenerates.
+    /// Only one refresh runs at a time per cache (and its clones), which
+    /// prevents thundering herd: if multiple threads detect expired credentials
+    /// simultaneously, the first one regenerates and the rest reuse its result.
+    /// If it fails, the next waiter tries again. Readers are not blocked while
+    /// V8 runs.
     pub async fn refresh_credentials(&self, http_client: &Client) -> Result<ApiCredentials> {
+        self.refresh_credentials_with_timing(http_client)
+            .await
+            .map(|(credentials, _)| credentials)
+    }
+
+    /// Refresh credentials and report how long each stage took.
+    ///
+    /// Same as [`refresh_credentials`](Self::refresh_credentials), but also returns
+    /// a [`RefreshTiming`] breakdown. Every stage also runs in its own `tracing`
+    /// span (`html_fetch`, `webpack_fetch`, `chunk_fetch`, `v8_init`,
+    /// `wasm_compile`, `sign_generation`) nested under a `refresh` span.
+    pub async fn refresh_credentials_with_timing(
+        &self,
+        http_client: &Client,
+    ) -> Result<(ApiCredentials, RefreshTiming)> {
+        let _flight = self.refresh_flight.lock().await;
+        let span = tracing::info_span!("refresh", elapsed_ms = tracing::field::Empty);
+        let (result, total) = match &self.sign_source {
+            Some(source) => timed(span, self.refresh_external(source.as_ref())).await,
+            None => timed(span, self.refresh_inner(http_client)).await,
+        };
+        let (credentials, mut timing) = result?;
+        timing.total = total;
+        Ok((credentials, timing))
+    }
+
+    async fn refresh_inner(&self, http_client: &Client) -> Result<(ApiCredentials, RefreshTiming)> {
+        let mut timing = RefreshTiming::default();
+
         // Step 1: Check if we need to refresh and get/fetch assets
         let (assets, yq_bid) = {
             let cache = self.inner.write().await;
 
             // Double-check: another thread may have regenerated while we waited
-            if let Some(ref creds) = cache.credentials
-                && cache
-                    .cached_assets
-                    .as_ref()
-                    .map(|a| a.is_fresh())
-                    .unwrap_or(false)
-            {
+            if let Some(creds) = cache.valid_credentials(false) {
                 eprintln!("[credential_cache] Another thread already refreshed credentials");
-                return Ok(creds.clone());
+                return Ok((creds.clone(), timing));
             }
 
             eprintln!("[credential_cache] Refreshing credentials...");
 
             // Fetch or reuse JS assets (1-hour cache)
-            if let Some(ref cached) = cache.cached_assets {
-                if cached.is_fresh() {
-                    eprintln!(
-                        "[credential_cache] Reusing cached JS assets (age: {:?})",
-                        cached.fetched_at.elapsed()
-                    );
-                    let assets = cached.clone();
-                    let yq_bid = cache.yq_bid.clone();
-                    (assets, yq_bid)
-                } else {
-                    eprintln!("[credential_cache] JS assets expired, re-fetching...");
-                    drop(cache); // Release lock before async operation
-                    let new_assets = js_fetcher::fetch_js_assets(http_client)
-                        .await
-                        .context("Failed to fetch JS assets from CDN")?;
-                    let mut cache = self.inner.write().await;
-                    cache.cached_assets = Some(new_assets.clone());
-                    let yq_bid = cache.yq_bid.clone();
-                    (new_assets, yq_bid)
-                }
+            if let Some(ref cached) = cache.cached_assets
+                && cached.is_fresh()
+            {
+                eprintln!(
+                    "[credential_cache] Reusing cached JS assets (age: {:?})",
+                    cached.fetched_at.elapsed()
+                );
+                (cached.clone(), cache.yq_bid.clone())
             } else {
-                eprintln!("[credential_cache] Fetching JS assets for first time...");
+                let yq_bid = cache.yq_bid.clone();
                 drop(cache); // Release lock before async operation
-                let new_assets = js_fetcher::fetch_js_assets(http_client)
+                let new_assets = self
+                    .load_assets(
+                        &TrackingPage::with_base(
+                            self.asset_client.as_ref().unwrap_or(http_client),
+                            &self.page_base,
+                            &self.locale,
+                        )
+                        .with_retry(self.asset_retry),
+                    )
                     .await
                     .context("Failed to fetch JS assets from CDN")?;
-                let mut cache = self.inner.write().await;
-                cache.cached_assets = Some(new_assets.clone());
-                let yq_bid = cache.yq_bid.clone();
+                timing.fetch = new_assets.fetch_timing;
                 (new_assets, yq_bid)
             }
         }; // Lock released here
@@ -169,19 +320,43 @@ impl CredentialCache {
         // Step 2: Generate credentials using V8 in a blocking task
         // V8 is not Send/Sync, so we run it in a dedicated blocking thread
         let sign_module_js = assets.sign_module_js.clone();
-        let sign = tokio::task::spawn_blocking(move || {
+        let chrome_profile = self.chrome_profile;
+        let fingerprint = self.fingerprint.clone();
+        let refresh_span = tracing::Span::current();
+        let watchdog = Arc::new(Mutex::new(V8Watchdog::default()));
+        let v8_watchdog = watchdog.clone();
+        let mut task = tokio::task::spawn_blocking(move || {
             use futures::executor::block_on;
 
+            let _refresh = refresh_span.enter();
+
             eprintln!("[credential_cache] Creating fresh V8 runtime...");
-            let mut generator = SignGenerator::new().context("Failed to create V8 runtime")?;
+            let (generator, v8_init) = timed_blocking(
+                tracing::info_span!("v8_init", elapsed_ms = tracing::field::Empty),
+                || SignGenerator::with_fingerprint(chrome_profile, &fingerprint),
+            );
+            let mut generator = generator.context("Failed to create V8 runtime")?;
+            {
+                let mut watchdog = v8_watchdog.lock().unwrap();
+                if watchdog.expired {
+                    anyhow::bail!("Sign generation timed out before the module ran");
+                }
+                watchdog.handle = Some(generator.termination_handle());
+            }
 
             eprintln!("[credential_cache] Initializing V8 runtime...");
-            block_on(generator.initialize(&sign_module_js))
-                .context("Failed to initialize sign module in V8")?;
+            let (initialized, wasm_compile) = timed_blocking(
+                tracing::info_span!("wasm_compile", elapsed_ms = tracing::field::Empty),
+                || block_on(generator.initialize(&sign_module_js)),
+            );
+            initialized.context("Failed to initialize sign module in V8")?;
 
             eprintln!("[credential_cache] Generating sign...");
-            let sign =
-                block_on(generator.generate_sign()).context("Failed to generate sign from V8")?;
+            let (sign, sign_generation) = timed_blocking(
+                tracing::info_span!("sign_generation", elapsed_ms = tracing::field::Empty),
+                || block_on(generator.generate_sign()),
+            );
+            let sign = sign.context("Failed to generate sign from V8")?;
 
             if sign.is_empty() {
                 anyhow::bail!("V8 returned empty sign");
@@ -189,10 +364,35 @@ impl CredentialCache {
 
             eprintln!("[credential_cache] Sign generated: {} chars", sign.len());
 
-            Ok::<String, anyhow::Error>(sign)
-        })
-        .await
-        .context("V8 task panicked")??;
+            Ok::<_, anyhow::Error>((sign, (v8_init, wasm_compile, sign_generation)))
+        });
+        let (sign, v8_timing) = match tokio::time::timeout(self.sign_timeout, &mut task).await {
+            Ok(joined) => joined.context("V8 task panicked")??,
+            Err(_) => {
+                eprintln!(
+                    "[credential_cache] Sign generation exceeded {:?}, terminating V8",
+                    self.sign_timeout
+                );
+                let handle = {
+                    let mut watchdog = watchdog.lock().unwrap();
+                    watchdog.expired = true;
+                    watchdog.handle.take()
+                };
+                if let Some(handle) = handle {
+                    handle.terminate_execution();
+                }
+                // Terminated JS unwinds promptly; don't hang if it doesn't
+                if tokio::time::timeout(V8_STOP_GRACE, task).await.is_err() {
+                    eprintln!("[credential_cache] V8 thread still running after termination");
+                }
+                return Err(TrackError::SignTimeout {
+                    timeout: self.sign_timeout,
+                }
+                .into());
+            }
+        };
+        (timing.v8_init, timing.wasm_compile, timing.sign_generation) = v8_timing;
+        timing.sign_len = self.check_sign_len(&sign);
 
         // Step 3: Store credentials in cache
         let credentials = ApiCredentials {
@@ -208,22 +408,106 @@ impl CredentialCache {
         } // Lock released
 
         eprintln!("[credential_cache] Credentials refreshed successfully");
-        Ok(credentials)
+        Ok((credentials, timing))
+    }
+
+    /// Obtain a sign from an external [`SignSource`], skipping assets and V8.
+    async fn refresh_external(
+        &self,
+        source: &dyn SignSource,
+    ) -> Result<(ApiCredentials, RefreshTiming)> {
+        let mut timing = RefreshTiming::default();
+        let yq_bid = {
+            let cache = self.inner.read().await;
+            if let Some(creds) = cache.valid_credentials(true) {
+                eprintln!("[credential_cache] Another thread already refreshed credentials");
+                return Ok((creds.clone(), timing));
+            }
+            cache.yq_bid.clone()
+        };
+
+        eprintln!("[credential_cache] Requesting sign from external source...");
+        let (sign, sign_generation) = timed(
+            tracing::info_span!("sign_generation", elapsed_ms = tracing::field::Empty),
+            source.generate_sign(),
+        )
+        .await;
+        let sign = sign.context("External sign source failed")?;
+        if sign.is_empty() {
+            anyhow::bail!("External sign source returned empty sign");
+        }
+        timing.sign_generation = sign_generation;
+        timing.sign_len = self.check_sign_len(&sign);
+
+        let credentials = ApiCredentials {
+            sign,
+            last_event_id: String::new(), // Computed per-request in make_request
+            yq_bid,
+            configs_md5: ConfigVersion::default(),
+        };
+        let mut cache = self.inner.write().await;
+        cache.credentials = Some(credentials.clone());
+        cache.external_sign_at = Some(Instant::now());
+
+        Ok((credentials, timing))
+    }
+
+    /// Log a warning when `sign` is suspiciously short or long (e.g., a partial
+    /// WASM memory read), which the API would reject with -11. Returns its length.
+    fn check_sign_len(&self, sign: &str) -> usize {
+        if let Some(warning) = sign_len_warning(sign.len(), &self.expected_sign_len) {
+            eprintln!("[credential_cache] WARNING: {}", warning);
+        }
+        sign.len()
+    }
+
+    /// Fetch fresh JS assets and store them in the cache.
+    ///
+    /// Expired or invalidated assets are passed along as the previous version, so
+    /// the sign module is only re-downloaded when its chunk hash changed.
+    pub(crate) async fn load_assets<F: AssetFetcher + ?Sized>(
+        &self,
+        fetcher: &F,
+    ) -> Result<JsAssets> {
+        let previous = {
+            let cache = self.inner.read().await;
+            cache
+                .cached_assets
+                .clone()
+                .or_else(|| cache.stale_assets.clone())
+        };
+
+        if previous.is_some() {
+            eprintln!("[credential_cache] JS assets expired or invalidated, re-fetching...");
+        } else {
+            eprintln!("[credential_cache] Fetching JS assets for first time...");
+        }
+
+        let assets = js_fetcher::refresh_js_assets(fetcher, previous.as_ref()).await?;
+
+        let mut cache = self.inner.write().await;
+        cache.cached_assets = Some(assets.clone());
+        cache.stale_assets = None;
+        Ok(assets)
     }
 
-    /// Invalidate the cache (credentials, assets, and runtime).
+    /// Invalidate the cache: clear credentials and mark the assets stale.
     ///
     /// This is called when the API returns error codes indicating credentials are expired:
     /// - Code -11 (invalid sign)
     /// - Code -14 (invalid session)
     /// - Code -5 (invalid uIP)
     ///
-    /// Dropping the cached runtime ensures fresh state for the next credential generation.
+    /// The next refresh always re-resolves the sign chunk from the tracking page; the
+    /// stale assets are kept only to skip re-downloading an unchanged sign module.
     pub async fn invalidate(&self) {
         let mut cache = self.inner.write().await;
         eprintln!("[credential_cache] Invalidating cache (assets + credentials)");
         cache.credentials = None;
-        cache.cached_assets = None;
+        cache.supplied = false;
+        if let Some(assets) = cache.cached_assets.take() {
+            cache.stale_assets = Some(assets);
+        }
     }
 
     /// Generate the Last-Event-ID for a specific request body.
@@ -231,25 +515,92 @@ impl CredentialCache {
     /// This must be called per-request because the header includes a hash of the body.
     /// Only needed when `guid` is empty (first request).
     pub async fn generate_last_event_id_for_body(&self, request_body_json: &str) -> Result<String> {
-        let cache = self.inner.read().await;
-
-        let configs_md5 = cache
-            .cached_assets
-            .as_ref()
-            .map(|a| a.configs_md5.clone())
-            .unwrap_or_else(|| "1.0.156".to_string());
-
-        let config = LastEventIdConfig {
-            yq_bid: cache.yq_bid.clone(),
-            configs_md5,
-            ..Default::default()
-        };
+        let config = self.last_event_id_config().await;
 
         Ok(last_event_id::generate_last_event_id(
             request_body_json,
             &config,
         ))
     }
+
+    /// Build the Last-Event-ID config, using the version the current sign was
+    /// generated against.
+    ///
+    /// If the cached assets were re-fetched with a different `configs.md5` since
+    /// the credentials were generated, the credentials' version wins so the
+    /// Last-Event-ID stays consistent with the sign.
+    async fn last_event_id_config(&self) -> LastEventIdConfig {
+        let cache = self.inner.read().await;
+
+        let assets_md5 = cache.cached_assets.as_ref().map(|a| &a.configs_md5);
+        let configs_md5 = match (cache.credentials.as_ref(), assets_md5) {
+            (Some(creds), Some(assets_md5)) if creds.configs_md5 != *assets_md5 => {
+                eprintln!(
+                    "[credential_cache] WARNING: configs.md5 mismatch (assets {}, credentials {}); \
+                     re-deriving Last-Event-ID from the credentials' version",
+                    assets_md5, creds.configs_md5
+                );
+                creds.configs_md5.clone()
+            }
+            (_, Some(assets_md5)) => assets_md5.clone(),
+            (Some(creds), None) => creds.configs_md5.clone(),
+            (None, None) => ConfigVersion::default(),
+        };
+
+        LastEventIdConfig::with_fingerprint(
+            cache.yq_bid.clone(),
+            configs_md5.to_string(),
+            &self.fingerprint,
+        )
+    }
+}
+
+/// Lets the async side stop the V8 runtime on the blocking thread when sign
+/// generation times out
+#[derive(Default)]
+struct V8Watchdog {
+    handle: Option<IsolateHandle>,
+    /// Set on timeout, so a runtime created afterwards never starts
+    expired: bool,
+}
+
+impl CredentialCacheInner {
+    /// Cached credentials, if still within their TTL (supplied ones have none)
+    fn valid_credentials(&self, external: bool) -> Option<&ApiCredentials> {
+        let fresh = if self.supplied {
+            true
+        } else if external {
+            self.external_sign_at
+                .is_some_and(|at| at.elapsed() < EXTERNAL_SIGN_TTL)
+        } else {
+            self.cached_assets.as_ref().is_some_and(|a| a.is_fresh())
+        };
+        self.credentials.as_ref().filter(|_| fresh)
+    }
+
+    /// Store caller-supplied credentials, keeping `_yq_bid` consistent between
+    /// the cookie and the Last-Event-ID
+    fn supply(&mut self, mut credentials: ApiCredentials) {
+        if credentials.yq_bid.is_empty() {
+            credentials.yq_bid = self.yq_bid.clone();
+        } else {
+            self.yq_bid = credentials.yq_bid.clone();
+        }
+        self.credentials = Some(credentials);
+        self.supplied = true;
+    }
+}
+
+/// Describe why a sign of `len` chars looks wrong, if it falls outside `expected`
+fn sign_len_warning(len: usize, expected: &RangeInclusive<usize>) -> Option<String> {
+    (!expected.contains(&len)).then(|| {
+        format!(
+            "sign length {} is outside the expected {}..={}; it may be truncated",
+            len,
+            expected.start(),
+            expected.end()
+        )
+    })
 }
 
 impl Default for CredentialCache {
@@ -261,6 +612,10 @@ impl Default for CredentialCache {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::js_fetcher::AssetManifest;
+    use crate::timing::FetchTiming;
+    use futures::future::BoxFuture;
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     #[tokio::test]
     async fn test_cache_creation() {
@@ -268,6 +623,249 @@ mod tests {
         assert!(cache.get_valid_credentials().await.is_none());
     }
 
+    struct MockFetcher {
+        chunk_url: std::sync::Mutex<String>,
+        downloads: AtomicU32,
+    }
+
+    impl MockFetcher {
+        fn new(chunk_url: &str) -> Self {
+            Self {
+                chunk_url: std::sync::Mutex::new(chunk_url.to_string()),
+                downloads: AtomicU32::new(0),
+            }
+        }
+    }
+
+    impl AssetFetcher for MockFetcher {
+        fn fetch_manifest(&self) -> BoxFuture<'_, Result<(AssetManifest, FetchTiming)>> {
+            let manifest = AssetManifest {
+                base_url: "https://static.17track.net/t/2026-01/_next/static/chunks/".to_string(),
+                configs_md5: "1.0.156".into(),
+                sign_chunk_url: self.chunk_url.lock().unwrap().clone(),
+            };
+            Box::pin(async move { Ok((manifest, FetchTiming::default())) })
+        }
+
+        fn fetch_sign_module<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<String>> {
+            self.downloads.fetch_add(1, Ordering::Relaxed);
+            let js = format!("// sign module from {}", url);
+            Box::pin(async move { Ok(js) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_redownloads_only_changed_chunk() {
+        let cache = CredentialCache::new();
+        let fetcher = MockFetcher::new("https://cdn/ff19fa74.aaaaaaaaaaaaaaaa.js");
+
+        let assets = cache.load_assets(&fetcher).await.unwrap();
+        assert_eq!(assets.chunk_hash, "aaaaaaaaaaaaaaaa");
+        assert_eq!(fetcher.downloads.load(Ordering::Relaxed), 1);
+
+        // Same deploy: manifest is re-checked but the module is reused
+        cache.invalidate().await;
+        cache.load_assets(&fetcher).await.unwrap();
+        assert_eq!(fetcher.downloads.load(Ordering::Relaxed), 1);
+
+        // New deploy: changed hash forces a re-download
+        cache.invalidate().await;
+        *fetcher.chunk_url.lock().unwrap() = "https://cdn/ff19fa74.bbbbbbbbbbbbbbbb.js".to_string();
+        let assets = cache.load_assets(&fetcher).await.unwrap();
+        assert_eq!(assets.chunk_hash, "bbbbbbbbbbbbbbbb");
+        assert!(assets.sign_module_js.contains("bbbbbbbbbbbbbbbb"));
+        assert_eq!(fetcher.downloads.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_config_version_mismatch_uses_credentials_version() {
+        let cache = CredentialCache::new();
+        let fetcher = MockFetcher::new("https://cdn/ff19fa74.aaaaaaaaaaaaaaaa.js");
+        cache.load_assets(&fetcher).await.unwrap();
+
+        // No credentials yet: the assets' version is used
+        let config = cache.last_event_id_config().await;
+        assert_eq!(config.configs_md5, "1.0.156");
+
+        // Sign generated against an older page version than the cached assets
+        cache.inner.write().await.credentials = Some(ApiCredentials {
+            sign: "sign".to_string(),
+            last_event_id: String::new(),
+            yq_bid: "bid".to_string(),
+            configs_md5: ConfigVersion::from("1.0.155"),
+        });
+        let config = cache.last_event_id_config().await;
+        assert_eq!(config.configs_md5, "1.0.155");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_assets_expire_after_ttl() {
+        let cache = CredentialCache::new();
+        let fetcher = MockFetcher::new("https://cdn/ff19fa74.aaaaaaaaaaaaaaaa.js");
+        cache.load_assets(&fetcher).await.unwrap();
+        cache.inner.write().await.credentials = Some(ApiCredentials {
+            sign: "sign".to_string(),
+            last_event_id: String::new(),
+            yq_bid: "bid".to_string(),
+            configs_md5: ConfigVersion::default(),
+        });
+        assert!(cache.get_valid_credentials().await.is_some());
+
+        tokio::time::advance(Duration::from_secs(3599)).await;
+        assert!(cache.get_valid_credentials().await.is_some());
+
+        // Credentials share the assets' 1-hour lifetime
+        tokio::time::advance(Duration::from_secs(2)).await;
+        assert!(
+            !cache
+                .inner
+                .read()
+                .await
+                .cached_assets
+                .as_ref()
+                .unwrap()
+                .is_fresh()
+        );
+        assert!(cache.get_valid_credentials().await.is_none());
+    }
+
+    struct FixedSign(AtomicU32);
+
+    impl SignSource for FixedSign {
+        fn generate_sign(&self) -> BoxFuture<'_, Result<String>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async { Ok("external-sign".to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_external_sign_source() {
+        let source = Arc::new(FixedSign(AtomicU32::new(0)));
+        let cache = CredentialCache::new().with_sign_source(source.clone());
+        // Never used: the external path fetches no assets
+        let http_client = Client::builder().build().unwrap();
+
+        let creds = cache.refresh_credentials(&http_client).await.unwrap();
+        assert_eq!(creds.sign, "external-sign");
+        assert_eq!(creds.configs_md5, ConfigVersion::default());
+
+        // Cached until invalidated
+        assert_eq!(
+            cache.get_valid_credentials().await.unwrap().sign,
+            "external-sign"
+        );
+        cache.refresh_credentials(&http_client).await.unwrap();
+        assert_eq!(source.0.load(Ordering::Relaxed), 1);
+
+        cache.invalidate().await;
+        assert!(cache.get_valid_credentials().await.is_none());
+        cache.refresh_credentials(&http_client).await.unwrap();
+        assert_eq!(source.0.load(Ordering::Relaxed), 2);
+    }
+
+    /// Counts calls and takes a while, so refreshes overlap
+    struct SlowSign(AtomicU32);
+
+    impl SignSource for SlowSign {
+        fn generate_sign(&self) -> BoxFuture<'_, Result<String>> {
+            let call = self.0.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(format!("sign-{}", call))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_refreshes_share_one_sign() {
+        let source = Arc::new(SlowSign(AtomicU32::new(0)));
+        let cache = CredentialCache::new().with_sign_source(source.clone());
+        let http_client = Client::builder().build().unwrap();
+
+        let refreshes: Vec<_> = (0..16)
+            .map(|_| {
+                let cache = cache.clone();
+                let http_client = http_client.clone();
+                tokio::spawn(async move { cache.refresh_credentials(&http_client).await })
+            })
+            .collect();
+        for refresh in refreshes {
+            assert_eq!(refresh.await.unwrap().unwrap().sign, "sign-0");
+        }
+        assert_eq!(source.0.load(Ordering::Relaxed), 1);
+    }
+
+    /// A webpack chunk whose module 4279 stands in for the wasm-bindgen sign
+    /// module: `default()` installs a fake WASM instance that writes a fixed
+    /// 300-character sign into its memory
+    const FAKE_SIGN_MODULE: &str = r#"
+        self.webpackChunk_N_E.push([[839], {
+            4279: function(module) {
+                var memory = { buffer: new ArrayBuffer(65536) };
+                module.exports = {
+                    get_fingerprint: function() {},
+                    default: async function() {
+                        globalThis.__wasmInstance = { exports: {
+                            memory: memory,
+                            __wbindgen_add_to_stack_pointer: function() { return 1024; },
+                            get_fingerprint: function(retptr) {
+                                var sign = "s".repeat(300);
+                                var u8 = new Uint8Array(memory.buffer);
+                                for (var i = 0; i < sign.length; i++) {
+                                    u8[2048 + i] = sign.charCodeAt(i);
+                                }
+                                var i32 = new Int32Array(memory.buffer);
+                                i32[retptr / 4] = 2048;
+                                i32[retptr / 4 + 1] = sign.length;
+                            }
+                        }};
+                    }
+                };
+            }
+        }]);
+    "#;
+
+    #[tokio::test]
+    async fn test_refresh_reports_stage_timing() {
+        use crate::js_fetcher::tests::{cdn, serve};
+
+        let base = serve(cdn(0, FAKE_SIGN_MODULE)).await;
+        let cache = CredentialCache::new().with_page_base(&base);
+
+        let (creds, timing) = cache
+            .refresh_credentials_with_timing(&Client::builder().build().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(creds.sign, "s".repeat(300));
+        assert_eq!(timing.sign_len, 300);
+        assert!(timing.fetch.html_fetch > Duration::ZERO);
+        assert!(timing.fetch.webpack_fetch > Duration::ZERO);
+        assert!(timing.fetch.chunk_fetch > Duration::ZERO);
+        assert!(timing.total > Duration::ZERO);
+        assert!(timing.total >= timing.fetch.html_fetch + timing.sign_generation);
+    }
+
+    #[tokio::test]
+    async fn test_sign_len_reported() {
+        let cache = CredentialCache::new().with_sign_source(Arc::new(FixedSign(AtomicU32::new(0))));
+        let http_client = Client::builder().build().unwrap();
+
+        let (creds, timing) = cache
+            .refresh_credentials_with_timing(&http_client)
+            .await
+            .unwrap();
+        assert_eq!(timing.sign_len, creds.sign.len());
+    }
+
+    #[test]
+    fn test_sign_len_warning() {
+        assert!(sign_len_warning(1200, &EXPECTED_SIGN_LEN).is_none());
+        // Accepted by the JS bounds check (1..=100000) but implausible
+        assert!(sign_len_warning(99_999, &EXPECTED_SIGN_LEN).is_some());
+        assert!(sign_len_warning(13, &EXPECTED_SIGN_LEN).is_some());
+        assert!(sign_len_warning(13, &(1..=20)).is_none());
+    }
+
     #[tokio::test]
     async fn test_invalidation() {
         let cache = CredentialCache::new();
@@ -277,4 +875,77 @@ mod tests {
 
         assert!(cache.get_valid_credentials().await.is_none());
     }
+
+    /// Serves a sign module that never finishes initializing
+    struct HangingModule;
+
+    impl AssetFetcher for HangingModule {
+        fn fetch_manifest(&self) -> BoxFuture<'_, Result<(AssetManifest, FetchTiming)>> {
+            let manifest = AssetManifest {
+                base_url: "https://cdn/".to_string(),
+                configs_md5: "1.0.156".into(),
+                sign_chunk_url: "https://cdn/ff19fa74.aaaaaaaaaaaaaaaa.js".to_string(),
+            };
+            Box::pin(async move { Ok((manifest, FetchTiming::default())) })
+        }
+
+        fn fetch_sign_module<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<String>> {
+            Box::pin(async { Ok("while (true) {}".to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sign_timeout_terminates_v8() {
+        let cache = CredentialCache::new().with_sign_timeout(Duration::from_millis(500));
+        cache.load_assets(&HangingModule).await.unwrap();
+
+        let started = std::time::Instant::now();
+        let err = cache
+            .refresh_credentials(&Client::builder().build().unwrap())
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<TrackError>(),
+            Some(&TrackError::SignTimeout {
+                timeout: Duration::from_millis(500)
+            })
+        );
+        // Returned once the runtime was stopped, not after the grace period
+        assert!(started.elapsed() < V8_STOP_GRACE);
+        assert!(cache.get_valid_credentials().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_assets_fetched_with_asset_client() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let app = axum::Router::new().fallback(move |headers: axum::http::HeaderMap| {
+            let client = headers
+                .get("x-client")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string();
+            recorded.lock().unwrap().push(client);
+            async { "<html></html>" }
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client_tagged = |tag: &'static str| {
+            let mut headers = wreq::header::HeaderMap::new();
+            headers.insert("x-client", wreq::header::HeaderValue::from_static(tag));
+            Client::builder().default_headers(headers).build().unwrap()
+        };
+        let cache = CredentialCache::new()
+            .with_page_base(&format!("http://{}", addr))
+            .with_asset_client(client_tagged("asset"));
+
+        // The page has no assets to extract, so the refresh itself fails
+        let _ = cache.refresh_credentials(&client_tagged("tracking")).await;
+        let seen = seen.lock().unwrap();
+        assert!(!seen.is_empty());
+        assert!(seen.iter().all(|client| client == "asset"), "{:?}", seen);
+    }
 }
@@ -0,0 +1,121 @@
+//! Typed errors for conditions callers may want to handle specifically.
+//!
+//! Client methods return `anyhow::Result`; these errors are carried inside the
+//! `anyhow::Error` and can be recovered with `downcast_ref`:
+//!
+//! ```
+//! use track17_rs::TrackError;
+//!
+//! let err: anyhow::Error = TrackError::Cancelled.into();
+//! assert!(matches!(err.downcast_ref::<TrackError>(), Some(TrackError::Cancelled)));
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrackError {
+    /// The caller cancelled tracking before it completed.
+    Cancelled,
+    /// 17track rate-limited the request (HTTP 429). `retry_after` is how long
+    /// the server asked us to wait.
+    RateLimited { retry_after: Duration },
+    /// The API kept rejecting credentials (code -11, -14 or -5) even after
+    /// `attempts` consecutive refreshes. `code` is the last rejection code.
+    CredentialRefreshExhausted { attempts: u32, code: i32 },
+    /// The API response body exceeded `limit` bytes and was abandoned
+    /// mid-read (see `Track17Config::max_response_bytes`).
+    ResponseTooLarge { limit: usize },
+    /// A JS asset request failed after `attempts` tries. Network errors,
+    /// timeouts, 5xx and 429 are retried; other 4xx fail on the first try.
+    /// The last failure is in the error chain.
+    AssetFetch { stage: AssetStage, attempts: u32 },
+    /// A JS asset arrived compressed (`encoding`, e.g. `"gzip"`) or otherwise
+    /// not as UTF-8 text, usually because a custom HTTP client doesn't
+    /// decompress responses.
+    AssetDecodeFailed {
+        stage: AssetStage,
+        encoding: Option<&'static str>,
+    },
+    /// A batch call was given no tracking numbers.
+    NoTrackingNumbers,
+    /// 17track reported it is down for maintenance (meta code 503).
+    Maintenance { message: String },
+    /// The sign module didn't initialize and produce a sign within `timeout`
+    /// (see `Track17Config::sign_timeout`); its V8 runtime was terminated.
+    SignTimeout { timeout: Duration },
+}
+
+/// Request of the JS asset fetch that failed (see [`TrackError::AssetFetch`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetStage {
+    TrackingPage,
+    WebpackRuntime,
+    SignModule,
+}
+
+impl fmt::Display for AssetStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::TrackingPage => "tracking page",
+            Self::WebpackRuntime => "webpack runtime",
+            Self::SignModule => "sign module",
+        })
+    }
+}
+
+impl fmt::Display for TrackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "Tracking was cancelled"),
+            Self::RateLimited { retry_after } => {
+                write!(f, "Rate limited by 17track (retry after {:?})", retry_after)
+            }
+            Self::CredentialRefreshExhausted { attempts, code } => {
+                write!(
+                    f,
+                    "API rejected credentials after {} refresh attempts (code {}). ",
+                    attempts, code
+                )?;
+                if *code == -5 {
+                    write!(
+                        f,
+                        "This is likely IP-based rate limiting (uIP), not expired credentials."
+                    )
+                } else {
+                    write!(f, "Credential generation may be broken.")
+                }
+            }
+            Self::ResponseTooLarge { limit } => {
+                write!(f, "API response exceeded {} bytes", limit)
+            }
+            Self::AssetFetch { stage, attempts } => {
+                let plural = if *attempts == 1 { "" } else { "s" };
+                write!(
+                    f,
+                    "Failed to fetch {} after {} attempt{}",
+                    stage, attempts, plural
+                )
+            }
+            Self::AssetDecodeFailed { stage, encoding } => {
+                match encoding {
+                    Some(encoding) => write!(f, "The {} is still {}-compressed", stage, encoding)?,
+                    None => write!(f, "The {} is not valid UTF-8 text", stage)?,
+                }
+                write!(
+                    f,
+                    "; the HTTP client must decompress responses (enable gzip, brotli and zstd)"
+                )
+            }
+            Self::NoTrackingNumbers => write!(f, "No tracking numbers given"),
+            Self::Maintenance { message } => {
+                write!(f, "17track is down for maintenance: {}", message)
+            }
+            Self::SignTimeout { timeout } => {
+                write!(f, "Sign generation timed out after {:?}", timeout)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TrackError {}
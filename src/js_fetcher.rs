@@ -11,14 +11,51 @@
 //! 4. Extract chunk 839's filename from the webpack runtime's `r.u` function
 //! 5. Fetch the sign generator chunk
 
-use std::time::Instant;
+use std::time::Duration;
+use tokio::time::Instant;
 
 use anyhow::{Context, Result};
+use futures::future::BoxFuture;
 use regex::Regex;
 use wreq::Client;
 
+use crate::credential::ConfigVersion;
+use crate::error::{AssetStage, TrackError};
+use crate::timing::{FetchTiming, timed};
+
 /// Base URL patterns for 17track's CDN.
-const TRACKING_PAGE_URL: &str = "https://t.17track.net/en";
+pub const TRACKING_PAGE_BASE: &str = "https://t.17track.net";
+const DEFAULT_LOCALE: &str = "en";
+
+/// Tracking page URL for a locale (e.g., `https://t.17track.net/fr`).
+pub fn tracking_page_url(locale: &str) -> String {
+    page_url(TRACKING_PAGE_BASE, locale)
+}
+
+fn page_url(base: &str, locale: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), locale)
+}
+
+/// Retry policy applied to each asset request (page, webpack runtime, sign module).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssetRetry {
+    /// Tries per request, including the first. Defaults to 3.
+    pub attempts: u32,
+    /// Delay before the first retry, doubling after each. Defaults to 500ms.
+    pub backoff: Duration,
+    /// Limit on each try, body included. Defaults to 15s.
+    pub timeout: Duration,
+}
+
+impl Default for AssetRetry {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(15),
+        }
+    }
+}
 
 /// Fetched JS assets and page configuration.
 #[derive(Clone, Debug)]
@@ -28,9 +65,13 @@ pub struct JsAssets {
     /// The CDN base URL (e.g., `https://static.17track.net/t/2026-01/_next/static/chunks/`).
     pub base_url: String,
     /// The `window.YQ.configs.md5` value extracted from the page HTML.
-    pub configs_md5: String,
-    /// When these assets were fetched.
+    pub configs_md5: ConfigVersion,
+    /// Content hash of the sign chunk (e.g., `aac6e850586820c7`); changes on every deploy.
+    pub chunk_hash: String,
+    /// When these assets were fetched (tokio clock, so pausable in tests).
     pub fetched_at: Instant,
+    /// How long each fetch stage took.
+    pub fetch_timing: FetchTiming,
 }
 
 impl JsAssets {
@@ -40,28 +81,239 @@ impl JsAssets {
     }
 }
 
+/// Location of the current sign module, resolved from the tracking page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetManifest {
+    pub base_url: String,
+    pub configs_md5: ConfigVersion,
+    pub sign_chunk_url: String,
+}
+
+/// Source of the tracking page manifest and sign module.
+///
+/// Implemented for `wreq::Client`; tests can provide a mock.
+pub trait AssetFetcher: Send + Sync {
+    /// Fetch the tracking page and webpack runtime and resolve the sign chunk URL.
+    /// Returns the HTML and webpack fetch durations alongside.
+    fn fetch_manifest(&self) -> BoxFuture<'_, Result<(AssetManifest, FetchTiming)>>;
+
+    /// Download the sign module JS.
+    fn fetch_sign_module<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<String>>;
+}
+
 /// Fetch JS assets from the 17track tracking page.
 ///
 /// 1. Fetches the tracking page HTML to discover chunk URLs and configs.md5
 /// 2. Fetches the webpack runtime to find chunk 839's filename
 /// 3. Downloads the sign generator chunk
 pub async fn fetch_js_assets(http_client: &Client) -> Result<JsAssets> {
-    eprintln!("[js_fetcher] Fetching tracking page...");
+    refresh_js_assets(&TrackingPage::new(http_client, DEFAULT_LOCALE), None).await
+}
 
-    // Step 1: Fetch the tracking page HTML
-    let html = http_client
-        .get(TRACKING_PAGE_URL)
-        .send()
-        .await
-        .context("Failed to fetch tracking page")?
-        .text()
+/// Resolve where the current sign module lives (chunk URL, configs.md5 and CDN
+/// base) without downloading it.
+///
+/// Only the small tracking page and webpack runtime are fetched; compare
+/// [`chunk_hash_from_url`] of the result against a cached copy to decide
+/// whether [`fetch_sign_chunk`] is needed.
+pub async fn resolve_sign_chunk_url<F: AssetFetcher + ?Sized>(
+    fetcher: &F,
+) -> Result<AssetManifest> {
+    let (manifest, _) = fetcher.fetch_manifest().await?;
+    Ok(manifest)
+}
+
+/// Download the sign module from a URL resolved by [`resolve_sign_chunk_url`].
+pub async fn fetch_sign_chunk<F: AssetFetcher + ?Sized>(fetcher: &F, url: &str) -> Result<String> {
+    fetcher.fetch_sign_module(url).await
+}
+
+/// Fetch JS assets, reusing `previous`'s sign module when its chunk hash is unchanged.
+///
+/// The tracking page and webpack runtime are always re-fetched so a new deploy
+/// is detected even while the old assets are within their TTL.
+pub async fn refresh_js_assets<F: AssetFetcher + ?Sized>(
+    fetcher: &F,
+    previous: Option<&JsAssets>,
+) -> Result<JsAssets> {
+    let (manifest, mut fetch_timing) = fetcher.fetch_manifest().await?;
+    let chunk_hash = chunk_hash_from_url(&manifest.sign_chunk_url);
+
+    let sign_module_js = match previous {
+        Some(prev) if prev.chunk_hash == chunk_hash => {
+            eprintln!(
+                "[js_fetcher] Sign module unchanged ({}), reusing cached copy",
+                chunk_hash
+            );
+            prev.sign_module_js.clone()
+        }
+        _ => {
+            if let Some(prev) = previous {
+                eprintln!(
+                    "[js_fetcher] Sign module version changed: {} -> {}",
+                    prev.chunk_hash, chunk_hash
+                );
+            }
+
+            // Step 6: Fetch the sign module JS
+            let (sign_module_js, chunk_fetch) = timed(
+                tracing::info_span!("chunk_fetch", elapsed_ms = tracing::field::Empty),
+                fetcher.fetch_sign_module(&manifest.sign_chunk_url),
+            )
+            .await;
+            fetch_timing.chunk_fetch = chunk_fetch;
+            let sign_module_js = sign_module_js?;
+
+            eprintln!(
+                "[js_fetcher] Sign module fetched, {} bytes",
+                sign_module_js.len()
+            );
+            sign_module_js
+        }
+    };
+
+    Ok(JsAssets {
+        sign_module_js,
+        base_url: manifest.base_url,
+        configs_md5: manifest.configs_md5,
+        chunk_hash,
+        fetched_at: Instant::now(),
+        fetch_timing,
+    })
+}
+
+/// Live [`AssetFetcher`]: the localized tracking page fetched with a `wreq::Client`.
+pub struct TrackingPage<'a> {
+    http_client: &'a Client,
+    url: String,
+    retry: AssetRetry,
+}
+
+impl<'a> TrackingPage<'a> {
+    pub fn new(http_client: &'a Client, locale: &str) -> Self {
+        Self::with_base(http_client, TRACKING_PAGE_BASE, locale)
+    }
+
+    /// Tracking page served from `base` instead of `https://t.17track.net`
+    /// (a mirror or a local mock server).
+    pub fn with_base(http_client: &'a Client, base: &str, locale: &str) -> Self {
+        Self {
+            http_client,
+            url: page_url(base, locale),
+            retry: AssetRetry::default(),
+        }
+    }
+
+    /// Retry each request per `retry` instead of the defaults.
+    pub fn with_retry(mut self, retry: AssetRetry) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+impl AssetFetcher for TrackingPage<'_> {
+    fn fetch_manifest(&self) -> BoxFuture<'_, Result<(AssetManifest, FetchTiming)>> {
+        Box::pin(fetch_manifest(self.http_client, &self.url, self.retry))
+    }
+
+    fn fetch_sign_module<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<String>> {
+        Box::pin(fetch_text(
+            self.http_client,
+            url,
+            AssetStage::SignModule,
+            self.retry,
+        ))
+    }
+}
+
+/// GET `url` as text, retrying failures with backoff. Fails with
+/// [`TrackError::AssetFetch`] once `retry.attempts` are used up, or at once
+/// for a failure retrying won't fix (see [`is_retryable`]).
+async fn fetch_text(
+    http_client: &Client,
+    url: &str,
+    stage: AssetStage,
+    retry: AssetRetry,
+) -> Result<String> {
+    let attempts = retry.attempts.max(1);
+    let mut delay = retry.backoff;
+    let mut attempt = 1;
+    loop {
+        let result = tokio::time::timeout(retry.timeout, async {
+            let response = http_client.get(url).send().await?.error_for_status()?;
+            Ok::<_, anyhow::Error>(response.bytes().await?)
+        })
         .await
-        .context("Failed to read tracking page body")?;
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("Timed out after {:?}", retry.timeout)));
+
+        match result {
+            // Refetching won't change how the client decodes the body
+            Ok(body) => return decode_text(body.to_vec(), stage).map_err(Into::into),
+            Err(e) if attempt >= attempts || !is_retryable(&e) => {
+                return Err(e.context(TrackError::AssetFetch {
+                    stage,
+                    attempts: attempt,
+                }));
+            }
+            Err(e) => {
+                eprintln!(
+                    "[js_fetcher] {} fetch failed ({}/{}): {}, retrying in {:?}",
+                    stage, attempt, attempts, e, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Timeouts, connection errors, 5xx, 408 and 429 may clear up; other 4xx
+/// (e.g., a 404 for a chunk that no longer exists) won't
+fn is_retryable(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<wreq::Error>()
+        .and_then(|e| e.status())
+        .is_none_or(|status| !status.is_client_error() || matches!(status.as_u16(), 408 | 429))
+}
+
+/// Body as UTF-8 text, rejecting bodies the HTTP client left compressed
+fn decode_text(body: Vec<u8>, stage: AssetStage) -> Result<String, TrackError> {
+    let encoding = match body.as_slice() {
+        [0x1f, 0x8b, ..] => Some("gzip"),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some("zstd"),
+        _ => None,
+    };
+    if encoding.is_some() {
+        return Err(TrackError::AssetDecodeFailed { stage, encoding });
+    }
+    String::from_utf8(body).map_err(|_| TrackError::AssetDecodeFailed {
+        stage,
+        encoding: None,
+    })
+}
+
+async fn fetch_manifest(
+    http_client: &Client,
+    page_url: &str,
+    retry: AssetRetry,
+) -> Result<(AssetManifest, FetchTiming)> {
+    eprintln!("[js_fetcher] Fetching tracking page {}...", page_url);
+
+    // Step 1: Fetch the tracking page HTML
+    let (html, html_fetch) = timed(
+        tracing::info_span!("html_fetch", elapsed_ms = tracing::field::Empty),
+        fetch_text(http_client, page_url, AssetStage::TrackingPage, retry),
+    )
+    .await;
+    let html = html?;
 
     eprintln!("[js_fetcher] Page fetched, {} bytes", html.len());
 
     // Step 2: Extract configs.md5 from inline script
-    let configs_md5 = extract_configs_md5(&html).unwrap_or_else(|| "1.0.156".to_string());
+    let configs_md5 = extract_configs_md5(&html)
+        .map(ConfigVersion::from)
+        .unwrap_or_default();
     eprintln!("[js_fetcher] configs.md5 = {}", configs_md5);
 
     // Step 3: Find the CDN base URL from script references
@@ -73,14 +325,17 @@ pub async fn fetch_js_assets(http_client: &Client) -> Result<JsAssets> {
         find_webpack_runtime_url(&html).context("Failed to find webpack runtime URL in HTML")?;
     eprintln!("[js_fetcher] Webpack runtime: {}", webpack_runtime_url);
 
-    let webpack_js = http_client
-        .get(&webpack_runtime_url)
-        .send()
-        .await
-        .context("Failed to fetch webpack runtime")?
-        .text()
-        .await
-        .context("Failed to read webpack runtime body")?;
+    let (webpack_js, webpack_fetch) = timed(
+        tracing::info_span!("webpack_fetch", elapsed_ms = tracing::field::Empty),
+        fetch_text(
+            http_client,
+            &webpack_runtime_url,
+            AssetStage::WebpackRuntime,
+            retry,
+        ),
+    )
+    .await;
+    let webpack_js = webpack_js?;
 
     eprintln!(
         "[js_fetcher] Webpack runtime fetched, {} bytes",
@@ -92,27 +347,30 @@ pub async fn fetch_js_assets(http_client: &Client) -> Result<JsAssets> {
         .context("Failed to find sign chunk URL in webpack runtime")?;
     eprintln!("[js_fetcher] Sign chunk URL: {}", sign_chunk_url);
 
-    // Step 6: Fetch the sign module JS
-    let sign_module_js = http_client
-        .get(&sign_chunk_url)
-        .send()
-        .await
-        .context("Failed to fetch sign module JS")?
-        .text()
-        .await
-        .context("Failed to read sign module body")?;
-
-    eprintln!(
-        "[js_fetcher] Sign module fetched, {} bytes",
-        sign_module_js.len()
-    );
+    Ok((
+        AssetManifest {
+            base_url,
+            configs_md5,
+            sign_chunk_url,
+        },
+        FetchTiming {
+            html_fetch,
+            webpack_fetch,
+            ..Default::default()
+        },
+    ))
+}
 
-    Ok(JsAssets {
-        sign_module_js,
-        base_url,
-        configs_md5,
-        fetched_at: Instant::now(),
-    })
+/// Extract the content hash from a chunk URL like `.../ff19fa74.aac6e850586820c7.js`.
+///
+/// Falls back to the whole file name if it doesn't have the `{name}.{hash}.js` shape.
+pub fn chunk_hash_from_url(url: &str) -> String {
+    let file = url.rsplit('/').next().unwrap_or(url);
+    let stem = file.strip_suffix(".js").unwrap_or(file);
+    match stem.split_once('.') {
+        Some((_, hash)) if !hash.is_empty() => hash.to_string(),
+        _ => stem.to_string(),
+    }
 }
 
 /// Extract `window.YQ.configs.md5` from the page HTML.
@@ -126,8 +384,9 @@ fn extract_configs_md5(html: &str) -> Option<String> {
 /// Extract the CDN base URL from script references in the HTML.
 ///
 /// Looks for patterns like `https://static.17track.net/t/2026-01/_next/static/chunks/`
+/// on any host, so mirrors and mock servers work too.
 fn extract_base_url(html: &str) -> Option<String> {
-    let re = Regex::new(r#"(https://static\.17track\.net/t/[^/]+/_next/static/chunks/)"#).ok()?;
+    let re = Regex::new(r#"(https?://[^/"'\s]+/t/[^/"'\s]+/_next/static/chunks/)"#).ok()?;
     re.captures(html)
         .and_then(|cap| cap.get(1))
         .map(|m| m.as_str().to_string())
@@ -157,9 +416,8 @@ fn find_webpack_runtime_url(html: &str) -> Option<String> {
         return Some(url.as_str().to_string());
     }
 
-    // Strategy 2: Look for webpack-*.js in static.17track.net URLs
-    let webpack_re =
-        Regex::new(r#"(https://static\.17track\.net/[^"]*webpack-[a-f0-9]+\.js)"#).ok()?;
+    // Strategy 2: Look for webpack-*.js in absolute CDN URLs
+    let webpack_re = Regex::new(r#"(https?://[^/"'\s]+/[^"]*webpack-[a-f0-9]+\.js)"#).ok()?;
     if let Some(cap) = webpack_re.captures(html)
         && let Some(url) = cap.get(1)
     {
@@ -207,7 +465,7 @@ fn find_sign_chunk_url_from_webpack(webpack_js: &str, base_url: &str) -> Option<
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
     #[test]
@@ -228,6 +486,14 @@ mod tests {
             extract_base_url(html),
             Some("https://static.17track.net/t/2026-01/_next/static/chunks/".to_string())
         );
+
+        // Mirrors and mock servers serve chunks from their own host
+        let html =
+            r#"src="http://127.0.0.1:8080/t/2026-01/_next/static/chunks/119-22a90af49d5bd9ee.js""#;
+        assert_eq!(
+            extract_base_url(html),
+            Some("http://127.0.0.1:8080/t/2026-01/_next/static/chunks/".to_string())
+        );
     }
 
     #[test]
@@ -259,6 +525,17 @@ mod tests {
         assert_eq!(url, Some(format!("{}ff19fa74.aac6e850586820c7.js", base)));
     }
 
+    #[test]
+    fn test_chunk_hash_from_url() {
+        assert_eq!(
+            chunk_hash_from_url(
+                "https://static.17track.net/t/2026-01/_next/static/chunks/ff19fa74.aac6e850586820c7.js"
+            ),
+            "aac6e850586820c7"
+        );
+        assert_eq!(chunk_hash_from_url("https://x/y/abc.js"), "abc");
+    }
+
     #[test]
     fn test_find_sign_chunk_direct_fallback() {
         let webpack_js = r#"something ff19fa74.aac6e850586820c7.js something"#;
@@ -268,4 +545,169 @@ mod tests {
             Some(format!("{}ff19fa74.aac6e850586820c7.js", base))
         );
     }
+
+    fn flaky_cdn(failures: u32) -> axum::Router {
+        cdn(failures, "// sign module")
+    }
+
+    /// Tracking page, webpack runtime and `sign_module` chunk, each path
+    /// failing with a 503 `failures` times first
+    pub(crate) fn cdn(failures: u32, sign_module: &'static str) -> axum::Router {
+        use axum::http::{StatusCode, Uri};
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+
+        let hits: Arc<Mutex<HashMap<String, u32>>> = Arc::default();
+        axum::Router::new().fallback(move |uri: Uri, headers: axum::http::HeaderMap| {
+            let hits = hits.clone();
+            async move {
+                let path = uri.path().to_string();
+                let count = {
+                    let mut hits = hits.lock().unwrap();
+                    let count = hits.entry(path.clone()).or_default();
+                    *count += 1;
+                    *count
+                };
+                if count <= failures {
+                    return (StatusCode::SERVICE_UNAVAILABLE, String::new());
+                }
+                let host = headers["host"].to_str().unwrap();
+                let chunks = format!("http://{}/t/2026-01/_next/static/chunks/", host);
+                let body = match path.as_str() {
+                    "/en" => format!(
+                        r#"<script src="{}webpack-abc123def456.js" id="_R_" async=""></script>"#,
+                        chunks
+                    ),
+                    p if p.ends_with("webpack-abc123def456.js") => {
+                        r#"({839:"ff19fa74"})[e]+"."+({839:"aac6e850586820c7"})[e]"#.to_string()
+                    }
+                    p if p.ends_with("ff19fa74.aac6e850586820c7.js") => sign_module.to_string(),
+                    _ => return (StatusCode::NOT_FOUND, String::new()),
+                };
+                (StatusCode::OK, body)
+            }
+        })
+    }
+
+    pub(crate) async fn serve(app: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    const FAST_RETRY: AssetRetry = AssetRetry {
+        attempts: 3,
+        backoff: Duration::from_millis(10),
+        timeout: Duration::from_secs(5),
+    };
+
+    #[tokio::test]
+    async fn test_resolve_sign_chunk_url_skips_chunk() {
+        let base = serve(flaky_cdn(0)).await;
+        let client = Client::builder().build().unwrap();
+        let page = TrackingPage::with_base(&client, &base, "en").with_retry(FAST_RETRY);
+
+        let manifest = resolve_sign_chunk_url(&page).await.unwrap();
+        assert_eq!(
+            manifest.sign_chunk_url,
+            format!(
+                "{}/t/2026-01/_next/static/chunks/ff19fa74.aac6e850586820c7.js",
+                base
+            )
+        );
+        assert_eq!(
+            manifest.base_url,
+            format!("{}/t/2026-01/_next/static/chunks/", base)
+        );
+        assert_eq!(
+            chunk_hash_from_url(&manifest.sign_chunk_url),
+            "aac6e850586820c7"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_sign_chunk() {
+        let base = serve(flaky_cdn(0)).await;
+        let client = Client::builder().build().unwrap();
+        let page = TrackingPage::with_base(&client, &base, "en").with_retry(FAST_RETRY);
+
+        let url = format!(
+            "{}/t/2026-01/_next/static/chunks/ff19fa74.aac6e850586820c7.js",
+            base
+        );
+        assert_eq!(
+            fetch_sign_chunk(&page, &url).await.unwrap(),
+            "// sign module"
+        );
+
+        // A 404 won't go away on retry, so it fails on the first attempt
+        let err = fetch_sign_chunk(&page, &format!("{}/missing.js", base))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TrackError>(),
+            Some(&TrackError::AssetFetch {
+                stage: AssetStage::SignModule,
+                attempts: 1,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_asset_fetch_retries_each_stage() {
+        // Every stage fails twice before succeeding
+        let base = serve(flaky_cdn(2)).await;
+        let client = Client::builder().build().unwrap();
+        let page = TrackingPage::with_base(&client, &base, "en").with_retry(FAST_RETRY);
+
+        let assets = refresh_js_assets(&page, None).await.unwrap();
+        assert_eq!(assets.sign_module_js, "// sign module");
+        assert_eq!(assets.chunk_hash, "aac6e850586820c7");
+    }
+
+    #[tokio::test]
+    async fn test_asset_fetch_reports_failed_stage() {
+        let base = serve(flaky_cdn(3)).await;
+        let client = Client::builder().build().unwrap();
+        let page = TrackingPage::with_base(&client, &base, "en").with_retry(FAST_RETRY);
+
+        let err = refresh_js_assets(&page, None).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TrackError>(),
+            Some(&TrackError::AssetFetch {
+                stage: AssetStage::TrackingPage,
+                attempts: 3,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compressed_asset_rejected() {
+        // A gzip body served without Content-Encoding, so the client can't
+        // know to decompress it
+        const GZIP_HELLO: &[u8] = &[
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x07, 0x00, 0x86, 0xa6, 0x10, 0x36, 0x05, 0x00, 0x00, 0x00,
+        ];
+        let base = serve(axum::Router::new().fallback(|| async { GZIP_HELLO })).await;
+        let client = Client::builder().build().unwrap();
+        let page = TrackingPage::with_base(&client, &base, "en").with_retry(FAST_RETRY);
+
+        let err = refresh_js_assets(&page, None).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<TrackError>(),
+            Some(&TrackError::AssetDecodeFailed {
+                stage: AssetStage::TrackingPage,
+                encoding: Some("gzip"),
+            })
+        );
+        assert!(err.to_string().contains("decompress"));
+
+        assert!(decode_text(vec![0xff, 0xfe], AssetStage::SignModule).is_err());
+        assert_eq!(
+            decode_text(b"// sign module".to_vec(), AssetStage::SignModule).unwrap(),
+            "// sign module"
+        );
+    }
 }
@@ -11,6 +11,9 @@
 use anyhow::Result;
 use deno_core::{JsRuntime, PollEventLoopOptions, RuntimeOptions};
 
+use crate::chrome_profile::ChromeProfile;
+use crate::last_event_id::FingerprintConfig;
+
 /// Browser mocks script that provides fake DOM/browser globals.
 ///
 /// The sign module probes various browser APIs during fingerprint generation.
@@ -39,6 +42,21 @@ pub struct SignGenerator {
 impl SignGenerator {
     /// Create a new V8 runtime with browser mocks.
     pub fn new() -> Result<Self> {
+        Self::with_profile(ChromeProfile::default())
+    }
+
+    /// Create a new V8 runtime whose browser mocks report `profile`'s
+    /// user agent and brands.
+    pub fn with_profile(profile: ChromeProfile) -> Result<Self> {
+        Self::with_fingerprint(profile, &FingerprintConfig::default())
+    }
+
+    /// Like [`with_profile`](Self::with_profile), with the canvas output taken
+    /// from `fingerprint`.
+    pub fn with_fingerprint(
+        profile: ChromeProfile,
+        fingerprint: &FingerprintConfig,
+    ) -> Result<Self> {
         let runtime = JsRuntime::new(RuntimeOptions::default());
 
         let mut generator = Self {
@@ -46,7 +64,15 @@ impl SignGenerator {
             initialized: false,
         };
 
-        // Install browser mocks
+        // Install browser mocks, after the Chrome version and fingerprint they read
+        generator
+            .runtime
+            .execute_script("[chrome_profile]", profile.mock_globals_script())
+            .map_err(|e| anyhow::anyhow!("Failed to install Chrome profile: {}", e))?;
+        generator
+            .runtime
+            .execute_script("[fingerprint]", fingerprint.mock_globals_script())
+            .map_err(|e| anyhow::anyhow!("Failed to install fingerprint: {}", e))?;
         generator
             .runtime
             .execute_script("[browser_mocks]", BROWSER_MOCKS)
@@ -217,25 +243,7 @@ impl SignGenerator {
             })()
         "#;
 
-        let result = self
-            .runtime
-            .execute_script("[read_sign]", read_script)
-            .map_err(|e| anyhow::anyhow!("Failed to read sign result: {}", e))?;
-
-        let json_str: String = {
-            let context = self.runtime.main_context();
-            let isolate = self.runtime.v8_isolate();
-            let mut handle_scope = deno_core::v8::HandleScope::new(isolate);
-            let handle_scope = unsafe { std::pin::Pin::new_unchecked(&mut handle_scope) };
-            let handle_scope = &mut handle_scope.init();
-            let context_local = deno_core::v8::Local::new(handle_scope, context);
-            let scope = &mut deno_core::v8::ContextScope::new(handle_scope, context_local);
-            let local = deno_core::v8::Local::new(scope, &result);
-            let str_val = local
-                .to_string(scope)
-                .ok_or_else(|| anyhow::anyhow!("V8 result is not a string"))?;
-            str_val.to_rust_string_lossy(scope)
-        };
+        let json_str = self.eval_string("[read_sign]", read_script)?;
 
         let parsed: serde_json::Value = serde_json::from_str(&json_str).map_err(|e| {
             anyhow::anyhow!(
@@ -256,6 +264,33 @@ impl SignGenerator {
             .ok_or_else(|| anyhow::anyhow!("Sign not found in result: {}", json_str))
     }
 
+    /// Run `script` and return its result as a string.
+    fn eval_string(&mut self, name: &'static str, script: &'static str) -> Result<String> {
+        let result = self
+            .runtime
+            .execute_script(name, script)
+            .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", name, e))?;
+
+        let context = self.runtime.main_context();
+        let isolate = self.runtime.v8_isolate();
+        let mut handle_scope = deno_core::v8::HandleScope::new(isolate);
+        let handle_scope = unsafe { std::pin::Pin::new_unchecked(&mut handle_scope) };
+        let handle_scope = &mut handle_scope.init();
+        let context_local = deno_core::v8::Local::new(handle_scope, context);
+        let scope = &mut deno_core::v8::ContextScope::new(handle_scope, context_local);
+        let local = deno_core::v8::Local::new(scope, &result);
+        let str_val = local
+            .to_string(scope)
+            .ok_or_else(|| anyhow::anyhow!("V8 result is not a string"))?;
+        Ok(str_val.to_rust_string_lossy(scope))
+    }
+
+    /// Handle that stops JS running in this runtime from another thread
+    /// (`terminate_execution`); the running call then fails.
+    pub fn termination_handle(&mut self) -> deno_core::v8::IsolateHandle {
+        self.runtime.v8_isolate().thread_safe_handle()
+    }
+
     /// Check if the runtime has been initialized with the sign module.
     ///
     /// Returns `true` if `initialize()` has been called successfully and the
@@ -264,3 +299,50 @@ impl SignGenerator {
         self.initialized
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mocks_match_chrome_profile() {
+        for profile in [ChromeProfile::Chrome141, ChromeProfile::Chrome143] {
+            let mut generator = SignGenerator::with_profile(profile).unwrap();
+            let user_agent = generator
+                .eval_string("[test_ua]", "navigator.userAgent")
+                .unwrap();
+            let brands = generator
+                .eval_string(
+                    "[test_brands]",
+                    "navigator.userAgentData.brands.map(b => b.version).join(',')",
+                )
+                .unwrap();
+
+            let major = profile.major_version();
+            assert_eq!(user_agent, profile.user_agent());
+            assert!(user_agent.contains(&format!("Chrome/{}.0.0.0", major)));
+            assert_eq!(brands, format!("{0},{0},99", major));
+        }
+    }
+
+    #[test]
+    fn test_mocks_report_canvas_data_url() {
+        let script = "document.createElement('canvas').toDataURL()";
+        let mut generator = SignGenerator::new().unwrap();
+        assert_eq!(
+            generator.eval_string("[test_canvas]", script).unwrap(),
+            crate::last_event_id::DEFAULT_CANVAS_DATA_URL
+        );
+
+        let fingerprint = FingerprintConfig {
+            canvas_data_url: Some("data:image/png;base64,c2Vzc2lvbi0y".to_string()),
+            ..Default::default()
+        };
+        let mut generator =
+            SignGenerator::with_fingerprint(ChromeProfile::default(), &fingerprint).unwrap();
+        assert_eq!(
+            generator.eval_string("[test_canvas]", script).unwrap(),
+            "data:image/png;base64,c2Vzc2lvbi0y"
+        );
+    }
+}
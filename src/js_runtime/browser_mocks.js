@@ -52,13 +52,20 @@ Object.setPrototypeOf(globalThis, Window.prototype);
     };
 })();
 
+// Chrome version, defined by ChromeProfile before this script runs so the
+// UA and brands always match the HTTP client's TLS fingerprint
+var __chrome = globalThis.__chromeProfile;
+
+// Per-session fingerprint values, defined by FingerprintConfig
+var __fingerprint = globalThis.__fingerprint;
+
 // Core globals
 globalThis.window = globalThis;
 globalThis.self = globalThis;
 
 // Navigator mock
 globalThis.navigator = {
-    userAgent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36",
+    userAgent: __chrome.userAgent,
     language: "en-US",
     languages: ["en-US", "en"],
     platform: "Win32",
@@ -70,7 +77,7 @@ globalThis.navigator = {
     vendor: "Google Inc.",
     vendorSub: "",
     productSub: "20030107",
-    appVersion: "5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/143.0.0.0 Safari/537.36",
+    appVersion: __chrome.userAgent.replace(/^Mozilla\//, ""),
     appName: "Netscape",
     appCodeName: "Mozilla",
     onLine: true,
@@ -84,8 +91,8 @@ globalThis.navigator = {
     permissions: { query: async function() { return { state: "prompt" }; } },
     userAgentData: {
         brands: [
-            { brand: "Chromium", version: "143" },
-            { brand: "Google Chrome", version: "143" },
+            { brand: "Chromium", version: __chrome.majorVersion },
+            { brand: "Google Chrome", version: __chrome.majorVersion },
             { brand: "Not?A_Brand", version: "99" }
         ],
         mobile: false,
@@ -96,10 +103,10 @@ globalThis.navigator = {
                 bitness: "64",
                 model: "",
                 platformVersion: "15.0.0",
-                uaFullVersion: "143.0.0.0",
+                uaFullVersion: __chrome.fullVersion,
                 fullVersionList: [
-                    { brand: "Chromium", version: "143.0.0.0" },
-                    { brand: "Google Chrome", version: "143.0.0.0" }
+                    { brand: "Chromium", version: __chrome.fullVersion },
+                    { brand: "Google Chrome", version: __chrome.fullVersion }
                 ]
             };
         }
@@ -199,7 +206,7 @@ function _createMockCanvas() {
         return ctx;
     };
     canvas.toDataURL = function() {
-        return "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+        return __fingerprint.canvasDataURL;
     };
     canvas.toBlob = function(cb) { cb(new Blob([""], {type: "image/png"})); };
     canvas.setAttribute = function(){};
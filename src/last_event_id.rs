@@ -20,13 +20,100 @@ use std::time::{SystemTime, UNIX_EPOCH};
 /// For a standard Windows Chrome environment (24-bit color, en-US, UTC-8, 1080x1920),
 /// we use a precomputed constant. The server doesn't validate the actual canvas content,
 /// just that the format is consistent.
-const DEFAULT_CANVAS_HASH: u32 = 1022200205;
+pub const DEFAULT_CANVAS_HASH: u32 = 1022200205;
+
+/// Canvas `toDataURL()` output the browser mocks report when
+/// [`FingerprintConfig::canvas_data_url`] is unset: a 1x1 PNG, identical for
+/// every session.
+pub const DEFAULT_CANVAS_DATA_URL: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
 
 /// Default timezone offset to use in the metadata string.
 /// This is the browser's `new Date().getTimezoneOffset()`, NOT the API's timeZoneOffset.
 /// 300 = UTC-5 (Eastern), 480 = UTC-8 (Pacific), etc.
 const DEFAULT_TZ_OFFSET: i32 = 300;
 
+/// Browser fingerprint inputs hashed into the canvas fingerprint.
+///
+/// These should match the values the sign module sees in `browser_mocks.js`
+/// so the Last-Event-ID and the sign describe the same environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FingerprintConfig {
+    /// `screen.colorDepth` (e.g., 24).
+    pub color_depth: u32,
+    /// `navigator.language` (e.g., `"en-US"`).
+    pub language: String,
+    /// Browser timezone offset from `new Date().getTimezoneOffset()` (e.g., 300 for EST).
+    pub tz_offset: i32,
+    /// `screen.width`.
+    pub screen_width: u32,
+    /// `screen.height`.
+    pub screen_height: u32,
+    /// Canvas `toDataURL()` output, reported by the browser mocks and hashed
+    /// into the Last-Event-ID. When `None`, the mocks return
+    /// [`DEFAULT_CANVAS_DATA_URL`] and the precomputed [`DEFAULT_CANVAS_HASH`]
+    /// captured from a real browser is used, since the canvas rendering behind
+    /// it was not recorded and cannot be recomputed.
+    ///
+    /// Sessions sharing a value share a canvas fingerprint; set one per client
+    /// (see [`with_canvas_data_url_from`](Self::with_canvas_data_url_from)) to
+    /// tell them apart.
+    pub canvas_data_url: Option<String>,
+}
+
+impl FingerprintConfig {
+    /// Use a canvas data URL picked at random from `pool`, e.g. renderings
+    /// captured from a few real browsers. An empty pool leaves it unchanged.
+    pub fn with_canvas_data_url_from<S: AsRef<str>>(mut self, pool: &[S]) -> Self {
+        if !pool.is_empty() {
+            let url = pool[fastrand::usize(..pool.len())].as_ref();
+            self.canvas_data_url = Some(url.to_string());
+        }
+        self
+    }
+
+    /// Script defining `globalThis.__fingerprint`, which `browser_mocks.js`
+    /// reads; it must run first.
+    pub(crate) fn mock_globals_script(&self) -> String {
+        let fingerprint = serde_json::json!({
+            "canvasDataURL": self.canvas_data_url.as_deref().unwrap_or(DEFAULT_CANVAS_DATA_URL),
+        });
+        format!("globalThis.__fingerprint = {};", fingerprint)
+    }
+}
+
+impl Default for FingerprintConfig {
+    fn default() -> Self {
+        Self {
+            color_depth: 24,
+            language: "en-US".to_string(),
+            tz_offset: DEFAULT_TZ_OFFSET,
+            screen_width: 1920,
+            screen_height: 1080,
+            canvas_data_url: None,
+        }
+    }
+}
+
+/// Compute the canvas fingerprint hash for a browser environment.
+///
+/// Hashes `{colorDepth}\r\n{language}\r\n{tzOffset}\r\n{height}x{width}\r\n{canvasDataURL}`
+/// with [`djb2`]. Falls back to [`DEFAULT_CANVAS_HASH`] when no canvas data URL is configured.
+pub fn compute_canvas_hash(config: &FingerprintConfig) -> u32 {
+    let Some(ref canvas_data_url) = config.canvas_data_url else {
+        return DEFAULT_CANVAS_HASH;
+    };
+
+    djb2(&format!(
+        "{}\r\n{}\r\n{}\r\n{}x{}\r\n{}",
+        config.color_depth,
+        config.language,
+        config.tz_offset,
+        config.screen_height,
+        config.screen_width,
+        canvas_data_url,
+    ))
+}
+
 /// DJB2 hash (seed 5381), iterating in reverse order.
 ///
 /// Matches the JS implementation:
@@ -105,6 +192,65 @@ fn hex_encode_chars(s: &str) -> String {
     result
 }
 
+/// A Last-Event-ID algorithm, named after the page `configs.md5` version it was
+/// reverse-engineered from.
+///
+/// [`generate_last_event_id`] picks the variant matching
+/// [`LastEventIdConfig::configs_md5`]; a new page version that changes the
+/// hashing gets its own variant rather than replacing an existing one.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastEventIdAlgorithm {
+    /// Reverse DJB2 canvas hash, murmur-like metadata/body hashes (`1.0.156`)
+    V1_0_156,
+}
+
+impl LastEventIdAlgorithm {
+    /// Every known algorithm, oldest first
+    pub const ALL: [LastEventIdAlgorithm; 1] = [Self::V1_0_156];
+
+    /// Newest known algorithm, used for unrecognized versions
+    pub const LATEST: LastEventIdAlgorithm = Self::V1_0_156;
+
+    /// The `configs.md5` version this algorithm was taken from
+    pub fn configs_version(self) -> &'static str {
+        match self {
+            Self::V1_0_156 => "1.0.156",
+        }
+    }
+
+    /// Algorithm for exactly this `configs.md5` version, if known
+    pub fn from_configs(configs_md5: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.configs_version() == configs_md5.trim())
+    }
+
+    /// Algorithm for `configs_md5`, falling back to [`LATEST`](Self::LATEST)
+    /// (with a warning) when the version is unknown
+    pub fn for_configs(configs_md5: &str) -> Self {
+        Self::from_configs(configs_md5).unwrap_or_else(|| {
+            eprintln!(
+                "[last_event_id] Unknown configs version {:?}, using the {} algorithm",
+                configs_md5,
+                Self::LATEST.configs_version()
+            );
+            Self::LATEST
+        })
+    }
+
+    fn generate(
+        self,
+        request_body_json: &str,
+        config: &LastEventIdConfig,
+        now: SystemTime,
+    ) -> String {
+        match self {
+            Self::V1_0_156 => encode(&build_metadata(config, now), request_body_json),
+        }
+    }
+}
+
 /// Configuration for Last-Event-ID generation.
 pub struct LastEventIdConfig {
     /// The `_yq_bid` device identifier (e.g., `"G-EA6CFDB403493F2A"`).
@@ -113,21 +259,36 @@ pub struct LastEventIdConfig {
     pub configs_md5: String,
     /// Browser timezone offset from `new Date().getTimezoneOffset()` (e.g., 300 for EST).
     pub tz_offset: i32,
-    /// DJB2 hash of the canvas fingerprint string. Use `DEFAULT_CANVAS_HASH` for standard env.
+    /// DJB2 hash of the canvas fingerprint string. See [`compute_canvas_hash`].
     pub canvas_hash: u32,
 }
 
-impl Default for LastEventIdConfig {
-    fn default() -> Self {
+impl LastEventIdConfig {
+    /// Build a config whose timezone and canvas hash match the given fingerprint.
+    pub fn with_fingerprint(
+        yq_bid: String,
+        configs_md5: String,
+        fingerprint: &FingerprintConfig,
+    ) -> Self {
         Self {
-            yq_bid: String::new(),
-            configs_md5: "1.0.156".to_string(),
-            tz_offset: DEFAULT_TZ_OFFSET,
-            canvas_hash: DEFAULT_CANVAS_HASH,
+            yq_bid,
+            configs_md5,
+            tz_offset: fingerprint.tz_offset,
+            canvas_hash: compute_canvas_hash(fingerprint),
         }
     }
 }
 
+impl Default for LastEventIdConfig {
+    fn default() -> Self {
+        Self::with_fingerprint(
+            String::new(),
+            LastEventIdAlgorithm::LATEST.configs_version().to_string(),
+            &FingerprintConfig::default(),
+        )
+    }
+}
+
 /// Generate the Last-Event-ID header value.
 ///
 /// # Arguments
@@ -136,60 +297,168 @@ impl Default for LastEventIdConfig {
 /// * `config` - Configuration with yq_bid, md5, timezone, and canvas hash.
 ///
 /// # Returns
-/// The hex-encoded Last-Event-ID string suitable for both the header and cookie.
+/// The hex-encoded Last-Event-ID string suitable for both the header and cookie,
+/// computed with the [`LastEventIdAlgorithm`] for `config.configs_md5`.
 pub fn generate_last_event_id(request_body_json: &str, config: &LastEventIdConfig) -> String {
-    // C array: [hex_encoded_reversed, _, _, domain_check, murmur_metadata, murmur_body]
-    // Indices used: C[0], C[3], C[4], C[5]
-
-    // Step 1: Hash request body -> C[5]
-    let body_hash = murmur_hash(request_body_json, request_body_json.len() as i32);
-    let c5 = pad8_hex(body_hash);
+    LastEventIdAlgorithm::for_configs(&config.configs_md5).generate(
+        request_body_json,
+        config,
+        SystemTime::now(),
+    )
+}
 
-    // Step 2: Canvas fingerprint hash (s) and I multiplier
+/// [`LastEventIdAlgorithm::V1_0_156`]: build the metadata string "a" that C[0] and C[4] are derived from:
+/// `{T}:false:{s}:0:0/{timestamp_hex}/11/true/{tz}/{s}/{md5}/{r}`
+fn build_metadata(config: &LastEventIdConfig, now: SystemTime) -> String {
+    // Canvas fingerprint hash (s)
     let s = config.canvas_hash;
     // I doubles from 5 to 10 after fingerprint (not used in output, just internal state)
 
-    // Step 3: Captcha hash (r) - normally 0 (no captcha on initial request)
+    // Captcha hash (r) - normally 0 (no captcha on initial request)
     let r: u32 = 0;
 
-    // Step 4: Build the metadata string "a"
     let timestamp_hex = format!(
         "{:x}",
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
+        now.duration_since(UNIX_EPOCH)
             .unwrap_or_default()
             .as_millis()
     );
 
-    // T = _yq_bid cookie value, or fall back to canvas hash string
-    let t_value = if config.yq_bid.is_empty() {
-        s.to_string()
-    } else {
-        config.yq_bid.clone()
-    };
-
     // webdriver = "false" (we're not a webdriver)
     // t = 0 (initial counter parameter)
     // S = 0 (global counter)
     // xhr = "true" (XMLHttpRequest available)
-    let a = format!(
+    format!(
         "{}:false:{}:0:0/{}/11/true/{}/{}/{}/{}",
-        t_value, s, timestamp_hex, config.tz_offset, s, config.configs_md5, r,
-    );
+        device_id(config),
+        s,
+        timestamp_hex,
+        config.tz_offset,
+        s,
+        config.configs_md5,
+        r,
+    )
+}
+
+/// T = _yq_bid cookie value, or fall back to canvas hash string
+fn device_id(config: &LastEventIdConfig) -> String {
+    if config.yq_bid.is_empty() {
+        config.canvas_hash.to_string()
+    } else {
+        config.yq_bid.clone()
+    }
+}
+
+/// [`LastEventIdAlgorithm::V1_0_156`]: assemble C[0] + C[3] + C[4] + C[5] for a
+/// metadata string and request body.
+fn encode(metadata: &str, request_body_json: &str) -> String {
+    // C array: [hex_encoded_reversed, _, _, domain_check, murmur_metadata, murmur_body]
+    // Indices used: C[0], C[3], C[4], C[5]
+
+    // Hash request body -> C[5]
+    let body_hash = murmur_hash(request_body_json, request_body_json.len() as i32);
+    let c5 = pad8_hex(body_hash);
 
-    // Step 5: Hash metadata string -> C[4], also sets C[3] = 4
-    let metadata_hash = murmur_hash(&a, 0);
-    let c4 = pad8_hex(metadata_hash);
-    let c3 = "4"; // Domain matches .17track.net
+    // Hash metadata string -> C[4], also sets C[3] = 4
+    let c4 = pad8_hex(murmur_hash(metadata, 0));
+    let c3 = DOMAIN_CHECK;
 
-    // Step 6: Reverse string and hex-encode -> C[0]
-    let reversed: String = a.chars().rev().collect();
+    // Reverse string and hex-encode -> C[0]
+    let reversed: String = metadata.chars().rev().collect();
     let c0 = hex_encode_chars(&reversed);
 
-    // Step 7: Assemble C[0] + C[3] + C[4] + C[5]
     format!("{}{}{}{}", c0, c3, c4, c5)
 }
 
+/// C[3] when the page's domain matches .17track.net
+const DOMAIN_CHECK: &str = "4";
+
+/// A Last-Event-ID split back into its parts. See [`decode`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedLastEventId {
+    /// The metadata string recovered from C[0]
+    /// (`{T}:false:{s}:0:0/{timestamp_hex}/11/true/{tz}/{s}/{md5}/{r}`).
+    pub metadata: String,
+    /// C[4]: hash of `metadata` as it appears in the value.
+    pub metadata_hash: u32,
+    /// C[5]: hash of the request body as it appears in the value.
+    pub body_hash: u32,
+}
+
+impl DecodedLastEventId {
+    /// Whether C[5] is the hash of `request_body_json`.
+    pub fn matches_body(&self, request_body_json: &str) -> bool {
+        self.body_hash == murmur_hash(request_body_json, request_body_json.len() as i32)
+    }
+}
+
+/// Split a Last-Event-ID into its metadata string and trailing hashes.
+///
+/// Returns `None` unless the value is C[0] (hex pairs of printable ASCII),
+/// C[3] = `4`, then two 8-hex-digit segments. Hashes are not checked; see
+/// [`validate`].
+pub fn decode(value: &str) -> Option<DecodedLastEventId> {
+    let split = value.len().checked_sub(17)?;
+    if !value.is_ascii() || split == 0 || split % 2 != 0 {
+        return None;
+    }
+    let (c0, tail) = value.split_at(split);
+    let (c3, hashes) = tail.split_at(1);
+    if c3 != DOMAIN_CHECK {
+        return None;
+    }
+    let (c4, c5) = hashes.split_at(8);
+    let hex8 = |segment: &str| {
+        segment
+            .bytes()
+            .all(|b| b.is_ascii_hexdigit())
+            .then(|| u32::from_str_radix(segment, 16).ok())
+            .flatten()
+    };
+
+    let reversed = (0..c0.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&c0[i..i + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    // hex_encode_chars only yields 2 digits per char for printable ASCII
+    if reversed.iter().any(|&b| !(0x20..0x7f).contains(&b)) {
+        return None;
+    }
+
+    Some(DecodedLastEventId {
+        metadata: reversed.iter().rev().map(|&b| b as char).collect(),
+        metadata_hash: hex8(c4)?,
+        body_hash: hex8(c5)?,
+    })
+}
+
+/// Check that `value` is structurally valid, its C[4] hash matches the
+/// embedded metadata, and the metadata was built from `config` (device id,
+/// canvas hash, timezone, configs version).
+///
+/// The body hash (C[5]) needs the request body; check it with
+/// [`DecodedLastEventId::matches_body`].
+pub fn validate(value: &str, config: &LastEventIdConfig) -> bool {
+    let Some(decoded) = decode(value) else {
+        return false;
+    };
+    if murmur_hash(&decoded.metadata, 0) != decoded.metadata_hash {
+        return false;
+    }
+
+    let parts: Vec<&str> = decoded.metadata.split('/').collect();
+    let [head, timestamp_hex, "11", "true", tz, canvas, md5, _captcha] = parts.as_slice() else {
+        return false;
+    };
+    let s = config.canvas_hash.to_string();
+    *head == format!("{}:false:{}:0:0", device_id(config), s)
+        && !timestamp_hex.is_empty()
+        && timestamp_hex.bytes().all(|b| b.is_ascii_hexdigit())
+        && *tz == config.tz_offset.to_string()
+        && *canvas == s
+        && *md5 == config.configs_md5
+}
+
 /// Generate the cookie string for the Last-Event-ID.
 ///
 /// Returns a cookie string like `"yq-=<value>;path=/;domain=17track.net"`
@@ -225,6 +494,43 @@ mod tests {
         assert_eq!(hash, 2087933171);
     }
 
+    #[test]
+    fn test_default_canvas_hash() {
+        assert_eq!(
+            compute_canvas_hash(&FingerprintConfig::default()),
+            1022200205
+        );
+        assert_eq!(LastEventIdConfig::default().canvas_hash, 1022200205);
+    }
+
+    #[test]
+    fn test_canvas_hash_from_data_url() {
+        let config = FingerprintConfig {
+            canvas_data_url: Some("data:image/png;base64,AAAA".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            compute_canvas_hash(&config),
+            djb2("24\r\nen-US\r\n300\r\n1080x1920\r\ndata:image/png;base64,AAAA")
+        );
+    }
+
+    #[test]
+    fn test_custom_canvas_data_url_reaches_mocks_and_hash() {
+        let default = FingerprintConfig::default();
+        assert!(
+            default
+                .mock_globals_script()
+                .contains(DEFAULT_CANVAS_DATA_URL)
+        );
+
+        let url = "data:image/png;base64,c2Vzc2lvbi0y";
+        let custom = FingerprintConfig::default().with_canvas_data_url_from(&[url]);
+        assert_eq!(custom.canvas_data_url.as_deref(), Some(url));
+        assert!(custom.mock_globals_script().contains(url));
+        assert_ne!(compute_canvas_hash(&custom), compute_canvas_hash(&default));
+    }
+
     #[test]
     fn test_murmur_empty() {
         assert_eq!(murmur_hash("", 0), 0);
@@ -277,6 +583,110 @@ mod tests {
         }
     }
 
+    fn test_config() -> LastEventIdConfig {
+        LastEventIdConfig {
+            yq_bid: "G-EA6CFDB403493F2A".to_string(),
+            configs_md5: "1.0.156".to_string(),
+            tz_offset: 300,
+            canvas_hash: DEFAULT_CANVAS_HASH,
+        }
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let config = test_config();
+        let body = r#"{"data":[{"num":"TEST123","fc":0,"sc":0}],"guid":"","timeZoneOffset":-480,"sign":"test"}"#;
+        let metadata = build_metadata(
+            &config,
+            UNIX_EPOCH + std::time::Duration::from_millis(1_771_000_000_000),
+        );
+
+        let decoded = decode(&encode(&metadata, body)).unwrap();
+        assert_eq!(decoded.metadata, metadata);
+        assert!(decoded.matches_body(body));
+        assert!(!decoded.matches_body("{}"));
+
+        let value = generate_last_event_id(body, &config);
+        assert!(validate(&value, &config));
+        let decoded = decode(&value).unwrap();
+        assert!(
+            decoded
+                .metadata
+                .starts_with("G-EA6CFDB403493F2A:false:1022200205:0:0/")
+        );
+        assert!(
+            decoded
+                .metadata
+                .ends_with("/11/true/300/1022200205/1.0.156/0")
+        );
+    }
+
+    #[test]
+    fn test_algorithm_dispatch() {
+        assert_eq!(
+            LastEventIdAlgorithm::from_configs("1.0.156"),
+            Some(LastEventIdAlgorithm::V1_0_156)
+        );
+        assert_eq!(LastEventIdAlgorithm::from_configs("9.9.999"), None);
+        // Unknown versions fall back to the latest algorithm
+        assert_eq!(
+            LastEventIdAlgorithm::for_configs("9.9.999"),
+            LastEventIdAlgorithm::LATEST
+        );
+
+        let config = LastEventIdConfig {
+            configs_md5: "9.9.999".to_string(),
+            ..test_config()
+        };
+        let now = UNIX_EPOCH + std::time::Duration::from_millis(1_771_000_000_000);
+        assert_eq!(
+            LastEventIdAlgorithm::for_configs(&config.configs_md5).generate("{}", &config, now),
+            LastEventIdAlgorithm::LATEST.generate("{}", &config, now)
+        );
+        let value = generate_last_event_id("{}", &config);
+        assert!(validate(&value, &config));
+        assert!(decode(&value).unwrap().metadata.contains("/9.9.999/"));
+    }
+
+    #[test]
+    fn test_validate_rejects_drift() {
+        let config = test_config();
+        let value = generate_last_event_id("{}", &config);
+
+        // Generated for a different page version
+        let other = LastEventIdConfig {
+            configs_md5: "1.0.157".to_string(),
+            ..test_config()
+        };
+        assert!(!validate(&value, &other));
+
+        // Tampered metadata no longer matches C[4]
+        let mut tampered = value.clone().into_bytes();
+        tampered[0] = if tampered[0] == b'3' { b'4' } else { b'3' };
+        assert!(!validate(&String::from_utf8(tampered).unwrap(), &config));
+
+        assert!(decode("").is_none());
+        assert!(decode("zz4039c888420b04e11").is_none());
+        // C[3] must be 4
+        let mut wrong_domain = value.clone();
+        let at = value.len() - 17;
+        wrong_domain.replace_range(at..at + 1, "5");
+        assert!(decode(&wrong_domain).is_none());
+    }
+
+    #[test]
+    fn test_decode_known_value() {
+        let value = "302f3635312e302e312f353032303032323230312f3030332f657572742f31312f36663964656436666239312f303a303a353032303032323230313a65736c61663a413246333934333034424446433641452d47420b04e11039c8884";
+        let decoded = decode(value).unwrap();
+        assert_eq!(
+            decoded.metadata,
+            "G-EA6CFDB403493F2A:false:1022200205:0:0/19bf6ded9f6/11/true/300/1022200205/1.0.156/0"
+        );
+        assert_eq!(decoded.metadata_hash, 0x20b04e11);
+        assert_eq!(decoded.body_hash, 0x039c8884);
+        assert!(validate(value, &test_config()));
+    }
+
     /// Test against the known-good value from the HAR file.
     ///
     /// The HAR shows that for a specific request with:
@@ -1,16 +1,52 @@
+#[cfg(feature = "v8")]
+pub mod blocking;
+#[cfg(feature = "v8")]
+pub mod chrome_profile;
+#[cfg(feature = "v8")]
 pub mod client;
+#[cfg(feature = "v8")]
 pub mod credential;
+#[cfg(feature = "v8")]
 pub mod credential_cache;
+pub mod error;
+#[cfg(feature = "v8")]
 pub mod js_fetcher;
+#[cfg(feature = "v8")]
 pub mod js_runtime;
 pub mod last_event_id;
+#[cfg(feature = "v8")]
+pub mod poll_strategy;
 pub mod proxy;
+#[cfg(feature = "v8")]
+pub mod sign_source;
+#[cfg(feature = "v8")]
+pub mod testing;
+#[cfg(feature = "v8")]
+pub mod timing;
+#[cfg(feature = "v8")]
+pub mod transport;
 pub mod types;
 pub mod yq_bid;
 pub mod zipcode;
 
-pub use client::{Track17Client, Track17Config};
+#[cfg(feature = "v8")]
+pub use chrome_profile::ChromeProfile;
+#[cfg(feature = "v8")]
+pub use client::{CredentialEvent, Track17Client, Track17Config, TrackTarget};
+#[cfg(feature = "v8")]
+pub use credential::ApiCredentials;
+#[cfg(feature = "v8")]
 pub use credential_cache::CredentialCache;
+pub use error::{AssetStage, TrackError};
+#[cfg(feature = "v8")]
+pub use poll_strategy::{DeadlinePollStrategy, DefaultPollStrategy, PollStrategy};
 pub use proxy::ProxyConfig;
-pub use types::{Meta, Shipment, TrackingItem, TrackingResponse, TrackingState, carriers};
-pub use zipcode::format_location;
+#[cfg(feature = "v8")]
+pub use sign_source::SignSource;
+#[cfg(feature = "v8")]
+pub use transport::TrackTransport;
+pub use types::{
+    Meta, MetaCode, OutcomeHint, Shipment, StateCategory, TrackingItem, TrackingResponse,
+    TrackingState, carriers,
+};
+pub use zipcode::{LocationResolver, format_location};
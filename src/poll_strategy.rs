@@ -0,0 +1,141 @@
+//! When to re-poll numbers that are still pending.
+//!
+//! 17track answers new numbers with a placeholder (code 100) while it queries
+//! the carrier, so the client polls until every number resolves. A
+//! [`PollStrategy`] decides how long to wait before each re-poll and when to
+//! give up; numbers still pending then are reported in
+//! [`TrackingResponse::incomplete`](crate::types::TrackingResponse::incomplete).
+//!
+//! Set one on [`Track17Config::poll_strategy`](crate::Track17Config::poll_strategy).
+//! The configured strategy is a prototype: every polling loop works on its
+//! own clone, so state such as a start time is per loop.
+
+use std::fmt;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Pending re-polls allowed by [`DefaultPollStrategy`]
+pub const DEFAULT_MAX_PENDING_RETRIES: u32 = 10; // Avoid long loops on invalid sessions
+/// Delay between re-polls used by [`DefaultPollStrategy`]
+pub const DEFAULT_PENDING_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Decides the delay before each re-poll of pending numbers.
+pub trait PollStrategy: PollStrategyClone + Send + Sync {
+    /// Delay before re-polling, or `None` to stop and accept what we have.
+    ///
+    /// `attempt` is the re-poll about to be made (1 for the first) and
+    /// `pending` the number of numbers still unresolved.
+    fn next_delay(&mut self, attempt: u32, pending: usize) -> Option<Duration>;
+}
+
+/// Boxed cloning for [`PollStrategy`]; implemented for every `Clone` strategy.
+pub trait PollStrategyClone {
+    fn clone_box(&self) -> Box<dyn PollStrategy>;
+}
+
+impl<T: PollStrategy + Clone + 'static> PollStrategyClone for T {
+    fn clone_box(&self) -> Box<dyn PollStrategy> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn PollStrategy> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl fmt::Debug for dyn PollStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PollStrategy")
+    }
+}
+
+/// Fixed delay between re-polls, up to a fixed number of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefaultPollStrategy {
+    pub max_retries: u32,
+    pub delay: Duration,
+}
+
+impl Default for DefaultPollStrategy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_PENDING_RETRIES,
+            delay: DEFAULT_PENDING_RETRY_DELAY,
+        }
+    }
+}
+
+impl PollStrategy for DefaultPollStrategy {
+    fn next_delay(&mut self, attempt: u32, _pending: usize) -> Option<Duration> {
+        (attempt <= self.max_retries).then_some(self.delay)
+    }
+}
+
+/// Re-poll every `delay` until `timeout` has passed since the first re-poll
+/// decision, however many attempts that takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlinePollStrategy {
+    timeout: Duration,
+    delay: Duration,
+    deadline: Option<Instant>,
+}
+
+impl DeadlinePollStrategy {
+    pub fn new(timeout: Duration, delay: Duration) -> Self {
+        Self {
+            timeout,
+            delay,
+            deadline: None,
+        }
+    }
+}
+
+impl PollStrategy for DeadlinePollStrategy {
+    fn next_delay(&mut self, _attempt: u32, _pending: usize) -> Option<Duration> {
+        let deadline = *self
+            .deadline
+            .get_or_insert_with(|| Instant::now() + self.timeout);
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        (!remaining.is_zero()).then(|| self.delay.min(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_strategy_stops_after_max_retries() {
+        let mut strategy = DefaultPollStrategy::default();
+        for attempt in 1..=DEFAULT_MAX_PENDING_RETRIES {
+            assert_eq!(
+                strategy.next_delay(attempt, 1),
+                Some(DEFAULT_PENDING_RETRY_DELAY)
+            );
+        }
+        assert_eq!(
+            strategy.next_delay(DEFAULT_MAX_PENDING_RETRIES + 1, 1),
+            None
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_deadline_strategy() {
+        let prototype = DeadlinePollStrategy::new(Duration::from_secs(5), Duration::from_secs(2));
+        let mut strategy: Box<dyn PollStrategy> = Box::new(prototype);
+
+        assert_eq!(strategy.next_delay(1, 3), Some(Duration::from_secs(2)));
+        tokio::time::advance(Duration::from_secs(4)).await;
+        // Only 1s left: the last wait is cut short
+        assert_eq!(strategy.next_delay(2, 3), Some(Duration::from_secs(1)));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(strategy.next_delay(3, 3), None);
+
+        // Clones of the prototype start their own clock
+        let mut fresh = prototype.clone_box();
+        assert_eq!(fresh.next_delay(1, 1), Some(Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,64 @@
+//! Pluggable sign generation.
+//!
+//! By default every process fetches the sign module and runs it in an embedded
+//! V8 runtime. Deployments with a dedicated sign service can implement
+//! [`SignSource`] instead and set it on
+//! [`Track17Config::sign_source`](crate::Track17Config::sign_source); the client
+//! then never starts V8.
+
+use std::fmt;
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use wreq::Client;
+
+use crate::credential_cache::CredentialCache;
+
+/// Produces fresh `sign` values for tracking requests.
+///
+/// Called whenever credentials are missing, expired (1 hour) or rejected by
+/// the API. The returned sign must be generated against the default
+/// `configs.md5` version (see [`crate::credential::ConfigVersion`]).
+pub trait SignSource: Send + Sync {
+    fn generate_sign(&self) -> BoxFuture<'_, Result<String>>;
+}
+
+impl fmt::Debug for dyn SignSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SignSource")
+    }
+}
+
+/// The built-in V8 path as a [`SignSource`].
+///
+/// Useful for running the sign service itself: serve `generate_sign` results
+/// to clients configured with an external source.
+#[derive(Clone)]
+pub struct V8SignSource {
+    http_client: Client,
+    credential_cache: CredentialCache,
+}
+
+impl V8SignSource {
+    pub fn new(http_client: Client, credential_cache: CredentialCache) -> Self {
+        Self {
+            http_client,
+            credential_cache,
+        }
+    }
+}
+
+impl SignSource for V8SignSource {
+    fn generate_sign(&self) -> BoxFuture<'_, Result<String>> {
+        Box::pin(async move {
+            if let Some(credentials) = self.credential_cache.get_valid_credentials().await {
+                return Ok(credentials.sign);
+            }
+            let credentials = self
+                .credential_cache
+                .refresh_credentials(&self.http_client)
+                .await?;
+            Ok(credentials.sign)
+        })
+    }
+}
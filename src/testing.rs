@@ -0,0 +1,458 @@
+//! Test helpers for exercising `Track17Client` without network or V8.
+//!
+//! [`MockTransport`] replays scripted API responses in order and records every
+//! request it receives, so the client's polling, carrier-suggestion, and
+//! credential-refresh logic can be tested deterministically.
+//! [`parse_har_response`] turns a captured session into a parser regression
+//! test. [`ResponseBuilder`] and [`ShipmentBuilder`] write the scripted
+//! responses without spelling out the nested JSON.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use track17_rs::Track17Client;
+//! use track17_rs::testing::{MockTransport, ResponseBuilder, ShipmentBuilder};
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let transport = Arc::new(MockTransport::new());
+//! transport.push_response(
+//!     ResponseBuilder::new("abc")
+//!         .shipment(ShipmentBuilder::new("NUM1").delivered_event("2024-01-15T10:30:00Z"))
+//!         .build(),
+//! );
+//!
+//! let client = Track17Client::with_transport(transport.clone());
+//! let response = client.track_multiple(&["NUM1".to_string()], 0).await?;
+//! assert!(response.shipments[0].is_delivered());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use futures::future::BoxFuture;
+use serde_json::{Value, json};
+use tokio::time::Instant;
+
+use crate::error::TrackError;
+use crate::transport::TrackTransport;
+use crate::types::{Shipment, TrackingItem, TrackingResponse, carriers};
+
+/// Parse the tracking API response out of a captured session.
+///
+/// Accepts a browser HAR (`log.entries[]`, bodies in `response.content.text`,
+/// optionally base64-encoded) or a single exchange written by
+/// `Track17Config::capture_dir`. When the capture polled several times, the
+/// last `/track/restapi` response is used, since that's the most complete.
+pub fn parse_har_response(har_json: &str) -> Result<TrackingResponse> {
+    let har: serde_json::Value = serde_json::from_str(har_json).context("invalid HAR JSON")?;
+    let entries = match har.pointer("/log/entries") {
+        Some(entries) => entries
+            .as_array()
+            .context("HAR log.entries is not an array")?
+            .iter()
+            .collect(),
+        None => vec![&har],
+    };
+
+    let entry = entries
+        .into_iter()
+        .rev()
+        .find(|entry| {
+            entry
+                .pointer("/request/url")
+                .and_then(|url| url.as_str())
+                .is_some_and(|url| url.contains("/track/restapi"))
+        })
+        .context("no /track/restapi entry in HAR")?;
+
+    let response = &entry["response"];
+    let (text, encoding) = match response.get("content") {
+        Some(content) => (&content["text"], content["encoding"].as_str()),
+        None => (&response["body"], None),
+    };
+    let text = text
+        .as_str()
+        .context("/track/restapi entry has no response body")?;
+    let body = match encoding {
+        Some("base64") => BASE64
+            .decode(text.trim())
+            .context("invalid base64 response body")?,
+        _ => text.as_bytes().to_vec(),
+    };
+
+    serde_json::from_slice(&body).context("response body is not a TrackingResponse")
+}
+
+/// Builds a [`Shipment`] as the API would send it.
+///
+/// Starts as a resolved (code 200) auto-detected shipment without events.
+/// Add events oldest first; the last one becomes `latest_event`.
+#[derive(Debug, Clone)]
+pub struct ShipmentBuilder {
+    number: String,
+    code: i32,
+    carrier: u32,
+    carrier_final: Option<u32>,
+    state: Option<String>,
+    state_final: Option<String>,
+    events: Vec<Value>,
+}
+
+impl ShipmentBuilder {
+    pub fn new(number: impl Into<String>) -> Self {
+        Self {
+            number: number.into(),
+            code: 200,
+            carrier: carriers::AUTO,
+            carrier_final: None,
+            state: None,
+            state_final: None,
+            events: Vec::new(),
+        }
+    }
+
+    /// Shipment code (100 still registering, 200 resolved, 400 not found)
+    pub fn code(mut self, code: i32) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn carrier(mut self, carrier: u32) -> Self {
+        self.carrier = carrier;
+        self
+    }
+
+    /// Carrier auto-detection settled on
+    pub fn carrier_final(mut self, carrier: u32) -> Self {
+        self.carrier_final = Some(carrier);
+        self
+    }
+
+    /// Shipment-level `state`, reported independently of events
+    pub fn state(mut self, state: impl Into<String>) -> Self {
+        self.state = Some(state.into());
+        self
+    }
+
+    /// Carrier-normalized shipment-level `state_final`
+    pub fn state_final(mut self, state: impl Into<String>) -> Self {
+        self.state_final = Some(state.into());
+        self
+    }
+
+    /// Add an event with a 17track `stage` (e.g., `"InTransit"`)
+    pub fn event(mut self, stage: &str, time_iso: &str, description: &str) -> Self {
+        self.events.push(json!({
+            "time_iso": time_iso,
+            "description": description,
+            "stage": stage,
+        }));
+        self
+    }
+
+    pub fn in_transit_event(self, time_iso: &str) -> Self {
+        self.event("InTransit", time_iso, "In transit")
+    }
+
+    pub fn delivered_event(self, time_iso: &str) -> Self {
+        self.event("Delivered", time_iso, "Delivered")
+    }
+
+    /// The shipment's JSON, as found in a response's `shipments`
+    pub fn to_json(&self) -> Value {
+        let mut shipment = json!({
+            "code": self.code,
+            "number": self.number,
+            "carrier": self.carrier,
+            "carrier_final": self.carrier_final,
+            "state": self.state,
+            "state_final": self.state_final,
+        });
+        if let Some(latest) = self.events.last() {
+            // The API lists events newest first
+            let events: Vec<&Value> = self.events.iter().rev().collect();
+            shipment["shipment"] = json!({
+                "tracking": {"providers": [{"events": events}]},
+                "latest_event": latest,
+            });
+        }
+        shipment
+    }
+
+    pub fn build(&self) -> Shipment {
+        serde_json::from_value(self.to_json()).expect("ShipmentBuilder produced invalid JSON")
+    }
+}
+
+/// Builds a [`TrackingResponse`] for [`MockTransport::push_response`].
+///
+/// Defaults to a successful (meta code 200) response with no shipments.
+#[derive(Debug, Clone)]
+pub struct ResponseBuilder {
+    guid: String,
+    meta_code: i32,
+    message: String,
+    shipments: Vec<ShipmentBuilder>,
+}
+
+impl ResponseBuilder {
+    /// A response in session `guid` (empty when 17track assigned none)
+    pub fn new(guid: impl Into<String>) -> Self {
+        Self {
+            guid: guid.into(),
+            meta_code: 200,
+            message: "Ok".to_string(),
+            shipments: Vec::new(),
+        }
+    }
+
+    pub fn shipment(mut self, shipment: ShipmentBuilder) -> Self {
+        self.shipments.push(shipment);
+        self
+    }
+
+    /// Response-level status (e.g., -11 for a rejected sign)
+    pub fn meta(mut self, code: i32, message: impl Into<String>) -> Self {
+        self.meta_code = code;
+        self.message = message.into();
+        self
+    }
+
+    pub fn to_json(&self) -> Value {
+        let shipments: Vec<Value> = self
+            .shipments
+            .iter()
+            .map(ShipmentBuilder::to_json)
+            .collect();
+        json!({
+            "id": 1,
+            "guid": self.guid,
+            "shipments": shipments,
+            "meta": {"code": self.meta_code, "message": self.message},
+        })
+    }
+
+    pub fn build(&self) -> TrackingResponse {
+        serde_json::from_value(self.to_json()).expect("ResponseBuilder produced invalid JSON")
+    }
+}
+
+/// A request observed by [`MockTransport`].
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub items: Vec<TrackingItem>,
+    pub guid: String,
+    /// When the request was made (tokio clock, so pausable in tests)
+    pub at: Instant,
+}
+
+/// Transport that returns scripted responses in FIFO order.
+///
+/// Requests made after the script is exhausted fail with an error.
+#[derive(Default)]
+pub struct MockTransport {
+    responses: Mutex<VecDeque<Result<TrackingResponse>>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+    invalidations: AtomicU32,
+    latency: Duration,
+    in_flight: AtomicU32,
+    max_in_flight: AtomicU32,
+    warm_up_error: Mutex<Option<TrackError>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delay every response by `latency` (tokio clock), simulating a slow API.
+    pub fn with_latency(latency: Duration) -> Self {
+        Self {
+            latency,
+            ..Self::default()
+        }
+    }
+
+    /// Queue a response.
+    pub fn push_response(&self, response: TrackingResponse) {
+        self.responses.lock().unwrap().push_back(Ok(response));
+    }
+
+    /// Queue a response from its JSON representation.
+    ///
+    /// Panics if the JSON does not deserialize into a `TrackingResponse`.
+    pub fn push_json(&self, json: serde_json::Value) {
+        let response = serde_json::from_value(json).expect("invalid TrackingResponse JSON");
+        self.push_response(response);
+    }
+
+    /// Queue a transport-level error (e.g., a network failure).
+    pub fn push_error(&self, message: &str) {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(Err(anyhow::anyhow!("{}", message)));
+    }
+
+    /// Queue an HTTP 429 rate-limit response carrying a `Retry-After` delay.
+    pub fn push_rate_limited(&self, retry_after: Duration) {
+        self.responses
+            .lock()
+            .unwrap()
+            .push_back(Err(TrackError::RateLimited { retry_after }.into()));
+    }
+
+    /// Make every `warm_up` fail with `error` (e.g., an unreachable CDN).
+    pub fn fail_warm_up(&self, error: TrackError) {
+        *self.warm_up_error.lock().unwrap() = Some(error);
+    }
+
+    /// All requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Most requests that were awaiting a response at the same time.
+    pub fn max_in_flight(&self) -> u32 {
+        self.max_in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Number of times the client asked to invalidate credentials.
+    pub fn invalidations(&self) -> u32 {
+        self.invalidations.load(Ordering::Relaxed)
+    }
+}
+
+/// Decrements the in-flight count when a request finishes or is dropped
+struct InFlightGuard<'a>(&'a AtomicU32);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl TrackTransport for MockTransport {
+    fn request<'a>(
+        &'a self,
+        items: &'a [TrackingItem],
+        guid: &'a str,
+    ) -> BoxFuture<'a, Result<TrackingResponse>> {
+        self.requests.lock().unwrap().push(RecordedRequest {
+            items: items.to_vec(),
+            guid: guid.to_string(),
+            at: Instant::now(),
+        });
+        let next = self.responses.lock().unwrap().pop_front();
+        let latency = self.latency;
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+        let guard = InFlightGuard(&self.in_flight);
+        Box::pin(async move {
+            let _guard = guard;
+            if !latency.is_zero() {
+                tokio::time::sleep(latency).await;
+            }
+            next.unwrap_or_else(|| Err(anyhow::anyhow!("MockTransport: no scripted response left")))
+        })
+    }
+
+    fn invalidate_credentials(&self) -> BoxFuture<'_, ()> {
+        self.invalidations.fetch_add(1, Ordering::Relaxed);
+        Box::pin(async {})
+    }
+
+    fn warm_up(&self) -> BoxFuture<'_, Result<()>> {
+        let error = self.warm_up_error.lock().unwrap().clone();
+        Box::pin(async move { error.map_or(Ok(()), |e| Err(e.into())) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builders() {
+        let shipment = ShipmentBuilder::new("NUM1")
+            .carrier(carriers::UPS)
+            .in_transit_event("2024-01-14T08:00:00Z")
+            .delivered_event("2024-01-15T10:30:00Z")
+            .build();
+        assert!(shipment.is_delivered());
+        assert_eq!(shipment.resolved_carrier(), carriers::UPS);
+        let details = shipment.shipment.as_ref().unwrap();
+        assert_eq!(details.events().len(), 2);
+        assert_eq!(
+            details.events()[0].time_iso.as_deref(),
+            Some("2024-01-15T10:30:00Z")
+        );
+
+        let pending = ShipmentBuilder::new("NUM2").code(100).build();
+        assert!(!pending.is_delivered());
+        assert!(pending.shipment.is_none());
+
+        let response = ResponseBuilder::new("abc")
+            .shipment(ShipmentBuilder::new("NUM1").delivered_event("2024-01-15T10:30:00Z"))
+            .shipment(ShipmentBuilder::new("NUM2").code(100))
+            .build();
+        assert_eq!(response.guid, "abc");
+        assert_eq!(response.meta.code, 200);
+        assert_eq!(response.shipments.len(), 2);
+
+        let rejected = ResponseBuilder::new("").meta(-11, "Invalid sign").build();
+        assert_eq!(rejected.meta.code, -11);
+        assert!(rejected.shipments.is_empty());
+    }
+
+    #[test]
+    fn test_parse_har_response() {
+        let body = r#"{"id":1,"guid":"abc","shipments":[{"code":200,"number":"NUM1","carrier":100002,"shipment":{"latest_event":{"time_iso":"2024-01-15T10:30:00Z","description":"Delivered","stage":"Delivered"}}}],"meta":{"code":200,"message":"Ok"}}"#;
+        let entry = |url: &str, content: serde_json::Value| {
+            serde_json::json!({
+                "request": {"method": "POST", "url": url},
+                "response": {"status": 200, "content": content},
+            })
+        };
+        let har = serde_json::json!({
+            "log": {
+                "version": "1.2",
+                "entries": [
+                    entry("https://t.17track.net/en", serde_json::json!({"text": "<html></html>"})),
+                    entry(
+                        "https://t.17track.net/track/restapi",
+                        serde_json::json!({
+                            "mimeType": "application/json",
+                            "text": BASE64.encode(body),
+                            "encoding": "base64",
+                        }),
+                    ),
+                ],
+            }
+        });
+
+        let response = parse_har_response(&har.to_string()).unwrap();
+        assert_eq!(response.guid, "abc");
+        assert_eq!(response.shipments.len(), 1);
+        assert_eq!(response.shipments[0].number, "NUM1");
+
+        // Plain-text bodies in the client's own capture format work too
+        let capture = serde_json::json!({
+            "request": {"method": "POST", "url": "http://127.0.0.1:8080/track/restapi"},
+            "response": {"status": 200, "body": body},
+        });
+        let response = parse_har_response(&capture.to_string()).unwrap();
+        assert_eq!(response.shipments[0].code, 200);
+
+        let err = parse_har_response(r#"{"log": {"entries": []}}"#).unwrap_err();
+        assert!(err.to_string().contains("no /track/restapi entry"));
+    }
+}
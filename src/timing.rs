@@ -0,0 +1,85 @@
+//! Stage timing for credential refresh.
+//!
+//! Each stage (HTML fetch, webpack fetch, chunk download, V8 init, WASM compile,
+//! sign generation) runs inside a `tracing` span with an `elapsed_ms` field, so
+//! a subscriber sees a per-stage breakdown. The same durations are returned as
+//! [`RefreshTiming`] for programmatic access.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use tracing::{Instrument, Span};
+
+/// Durations of the JS asset fetch stages.
+///
+/// All zero when cached assets were reused.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FetchTiming {
+    pub html_fetch: Duration,
+    pub webpack_fetch: Duration,
+    pub chunk_fetch: Duration,
+}
+
+/// Per-stage durations of a credential refresh.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RefreshTiming {
+    pub fetch: FetchTiming,
+    /// Creating the V8 runtime and installing browser mocks.
+    pub v8_init: Duration,
+    /// Executing the sign module and compiling its WASM.
+    pub wasm_compile: Duration,
+    /// Calling `get_fingerprint`.
+    pub sign_generation: Duration,
+    /// Length of the new sign; 0 when another refresh's credentials were reused.
+    pub sign_len: usize,
+    /// Whole refresh, including lock waits.
+    pub total: Duration,
+}
+
+/// Run `fut` inside `span`, recording its duration in the span's `elapsed_ms` field.
+///
+/// The span must declare `elapsed_ms = tracing::field::Empty`.
+pub(crate) async fn timed<F: Future>(span: Span, fut: F) -> (F::Output, Duration) {
+    let start = Instant::now();
+    let output = fut.instrument(span.clone()).await;
+    let elapsed = start.elapsed();
+    span.record("elapsed_ms", elapsed.as_secs_f64() * 1000.0);
+    (output, elapsed)
+}
+
+/// Blocking counterpart of [`timed`] for work inside `spawn_blocking`.
+pub(crate) fn timed_blocking<T>(span: Span, f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let output = span.in_scope(f);
+    let elapsed = start.elapsed();
+    span.record("elapsed_ms", elapsed.as_secs_f64() * 1000.0);
+    (output, elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_timed_records_duration() {
+        let span = tracing::info_span!("stage", elapsed_ms = tracing::field::Empty);
+        let (value, elapsed) = timed(span, async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            42
+        })
+        .await;
+        assert_eq!(value, 42);
+        assert!(elapsed >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_timed_blocking_records_duration() {
+        let span = tracing::info_span!("stage", elapsed_ms = tracing::field::Empty);
+        let (value, elapsed) = timed_blocking(span, || {
+            std::thread::sleep(Duration::from_millis(5));
+            "done"
+        });
+        assert_eq!(value, "done");
+        assert!(elapsed >= Duration::from_millis(5));
+    }
+}
@@ -0,0 +1,622 @@
+//! Transport layer for tracking API requests.
+//!
+//! `Track17Client` drives the polling/retry state machine; a `TrackTransport`
+//! performs a single request round-trip. The default `HttpTransport` signs
+//! requests with V8-generated credentials and sends them via `wreq`. Tests can
+//! substitute [`crate::testing::MockTransport`] to script responses.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde_json::json;
+use time::PrimitiveDateTime;
+use time::macros::format_description;
+use tokio::time::Instant;
+use wreq::{Client, header};
+
+use crate::credential::ApiCredentials;
+use crate::credential_cache::CredentialCache;
+use crate::error::TrackError;
+use crate::proxy::ProxyConfig;
+use crate::types::{TrackingItem, TrackingRequest, TrackingResponse};
+
+const ORIGIN: &str = "https://t.17track.net";
+const API_PATH: &str = "/track/restapi";
+const TOO_MANY_REQUESTS: u16 = 429;
+/// Wait used when a 429 carries no (parseable) Retry-After header
+pub(crate) const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(10);
+pub(crate) const DEFAULT_MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+/// Headers the transport sets itself; extra headers can't replace them
+const RESERVED_HEADERS: &[&str] = &[
+    "cookie",
+    "origin",
+    "referer",
+    "last-event-id",
+    "content-type",
+    "content-length",
+    "host",
+];
+
+/// A single round-trip to the tracking API.
+pub trait TrackTransport: Send + Sync {
+    /// Send one request for `items`, continuing the session identified by `guid`
+    /// (empty on the first request).
+    fn request<'a>(
+        &'a self,
+        items: &'a [TrackingItem],
+        guid: &'a str,
+    ) -> BoxFuture<'a, Result<TrackingResponse>>;
+
+    /// Drop any cached credentials after the API rejected them
+    /// (codes -11, -14, -5). The next `request` must use fresh credentials.
+    fn invalidate_credentials(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+
+    /// Use credentials extracted out-of-band until the API rejects them.
+    /// Transports without a credential cache ignore them.
+    fn set_credentials(&self, _credentials: ApiCredentials) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+
+    /// Prepare credentials ahead of the first `request` so it doesn't pay
+    /// the generation cost.
+    fn warm_up(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Live transport: V8-generated credentials + HTTP via `wreq`.
+#[derive(Clone)]
+pub struct HttpTransport {
+    http_client: Client,
+    credential_cache: CredentialCache,
+    locale: String,
+    country: String,
+    /// Second description language sent as `lang`, if any
+    translation_lang: Option<String>,
+    /// Origin of the tracking API and page (`https://t.17track.net` unless overridden)
+    api_base: String,
+    max_response_bytes: usize,
+    /// Caller headers appended to every tracking request
+    extra_headers: Vec<(String, String)>,
+    /// Where request/response captures go, if enabled
+    capture: Option<Arc<Capture>>,
+}
+
+/// Destination for HAR-style request/response captures
+struct Capture {
+    dir: PathBuf,
+    /// Proxy the requests went through, password masked
+    proxy: Option<String>,
+    seq: AtomicU64,
+}
+
+impl HttpTransport {
+    pub fn new(http_client: Client, credential_cache: CredentialCache) -> Self {
+        Self {
+            http_client,
+            credential_cache,
+            locale: "en".to_string(),
+            country: "US".to_string(),
+            translation_lang: None,
+            api_base: ORIGIN.to_string(),
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            extra_headers: Vec::new(),
+            capture: None,
+        }
+    }
+
+    /// Send requests to `base` (e.g., `http://127.0.0.1:8080` for a mock
+    /// server) instead of `https://t.17track.net`.
+    pub fn with_api_base(mut self, base: impl Into<String>) -> Self {
+        self.api_base = base.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Abort responses whose body grows past `limit` bytes with
+    /// [`TrackError::ResponseTooLarge`]. Defaults to 16 MiB.
+    pub fn with_max_response_bytes(mut self, limit: usize) -> Self {
+        self.max_response_bytes = limit;
+        self
+    }
+
+    /// Write every request/response pair to `dir` as a timestamped JSON file
+    /// (URL, headers, bodies, status, timing). `proxy` is recorded with its
+    /// password masked. For debugging API changes; the files hold live
+    /// session cookies.
+    pub fn with_capture_dir(
+        mut self,
+        dir: impl Into<PathBuf>,
+        proxy: Option<&ProxyConfig>,
+    ) -> Self {
+        self.capture = Some(Arc::new(Capture {
+            dir: dir.into(),
+            proxy: proxy.map(ProxyConfig::to_redacted_string),
+            seq: AtomicU64::new(0),
+        }));
+        self
+    }
+
+    /// Send requests as a visitor from `country` browsing the `locale` site.
+    /// 17track localizes event descriptions based on the culture cookie.
+    pub fn with_locale(mut self, locale: impl Into<String>, country: impl Into<String>) -> Self {
+        self.locale = locale.into();
+        self.country = country.into();
+        self
+    }
+
+    /// Ask for event descriptions translated into `lang` as well; the
+    /// carrier's original text is kept in
+    /// [`TrackingEvent::description_original`](crate::types::TrackingEvent::description_original).
+    pub fn with_translation_lang(mut self, lang: impl Into<String>) -> Self {
+        self.translation_lang = Some(lang.into());
+        self
+    }
+
+    /// Append `headers` to every tracking request (e.g., a proxy session
+    /// token). Headers failing [`validate_extra_headers`] are dropped with a
+    /// warning.
+    pub fn with_extra_headers(mut self, headers: &[(String, String)]) -> Self {
+        self.extra_headers = headers
+            .iter()
+            .filter(|(name, value)| match check_extra_header(name, value) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("[track17-req] Ignoring extra header: {:#}", e);
+                    false
+                }
+            })
+            .cloned()
+            .collect();
+        self
+    }
+
+    fn cookies(&self, yq_bid: &str, last_event_id: &str) -> String {
+        format!(
+            "country={}; _yq_bid={}; v5_Culture={}; Last-Event-ID={}",
+            self.country, yq_bid, self.locale, last_event_id
+        )
+    }
+
+    fn referer(&self) -> String {
+        format!("{}/{}", self.api_base, self.locale)
+    }
+
+    fn api_url(&self) -> String {
+        format!("{}{}", self.api_base, API_PATH)
+    }
+
+    /// Ensure credentials are valid, regenerating if needed.
+    ///
+    /// Fast path (read lock): Returns cached credentials if still valid
+    /// Slow path (write lock): Generates fresh credentials via V8
+    async fn ensure_credentials(&self) -> Result<ApiCredentials> {
+        // Fast path: read lock, check if valid
+        if let Some(creds) = self.credential_cache.get_valid_credentials().await {
+            return Ok(creds);
+        }
+
+        // Slow path: write lock, regenerate
+        eprintln!("Generating credentials via V8...");
+        let credentials = self
+            .credential_cache
+            .refresh_credentials(&self.http_client)
+            .await?;
+        eprintln!("Credentials generated!");
+
+        Ok(credentials)
+    }
+
+    /// Headers for a tracking request: the session's own, then the caller's
+    /// extras. `last_event_id` is only sent when non-empty (first request).
+    fn request_headers(&self, cookies: String, last_event_id: String) -> Vec<(&str, String)> {
+        let mut headers = vec![
+            (header::REFERER.as_str(), self.referer()),
+            (header::COOKIE.as_str(), cookies),
+            (header::ORIGIN.as_str(), self.api_base.clone()),
+        ];
+        if !last_event_id.is_empty() {
+            headers.push(("last-event-id", last_event_id));
+        }
+        headers.extend(
+            self.extra_headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.clone())),
+        );
+        headers
+    }
+
+    /// Make a single API request for tracking numbers
+    async fn make_request(&self, items: &[TrackingItem], guid: &str) -> Result<TrackingResponse> {
+        let creds = self.ensure_credentials().await?;
+
+        // Log request details
+        eprintln!(
+            "[track17-req] items={:?}, guid={}, sign_len={}, yq_bid_len={}",
+            items
+                .iter()
+                .map(|i| format!("{}:{}", i.num, i.fc))
+                .collect::<Vec<_>>(),
+            if guid.is_empty() {
+                "(empty)"
+            } else {
+                &guid[..guid.len().min(8)]
+            },
+            creds.sign.len(),
+            creds.yq_bid.len(),
+        );
+
+        let request = TrackingRequest {
+            data: items.to_vec(),
+            guid: guid.to_string(),
+            time_zone_offset: -480,
+            sign: creds.sign.clone(),
+            lang: self.translation_lang.clone(),
+        };
+
+        let request_body = request.to_wire_body();
+
+        // Generate Last-Event-ID from the request body (only meaningful when guid is empty)
+        let last_event_id = if guid.is_empty() {
+            self.credential_cache
+                .generate_last_event_id_for_body(&request_body)
+                .await?
+        } else {
+            String::new()
+        };
+
+        let cookies = self.cookies(&creds.yq_bid, &last_event_id);
+        let request_headers = self.request_headers(cookies, last_event_id);
+
+        let url = self.api_url();
+        let mut req = self.http_client.post(&url);
+        for (name, value) in &request_headers {
+            req = req.header(*name, value);
+        }
+
+        // Copies for the capture file are only made when capturing
+        let capturing = self.capture.is_some();
+        let captured_request_body = capturing.then(|| request_body.clone());
+
+        let started = Instant::now();
+        let mut response = req.body(request_body).send().await?;
+
+        let status = response.status();
+        let response_headers: Vec<(String, String)> = if capturing {
+            response
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                    (name.to_string(), value)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let capture = |body: &[u8], error: Option<String>| {
+            let request_body = captured_request_body.as_ref()?;
+            let mut exchange = json!({
+                "elapsed_ms": started.elapsed().as_millis() as u64,
+                "request": {
+                    "method": "POST",
+                    "url": url,
+                    "headers": har_headers(&request_headers),
+                    "body": request_body,
+                },
+                "response": {
+                    "status": status.as_u16(),
+                    "headers": har_headers(&response_headers),
+                    "body": String::from_utf8_lossy(body),
+                },
+            });
+            if let Some(error) = error {
+                exchange["error"] = json!(error);
+            }
+            Some(exchange)
+        };
+
+        if status.as_u16() == TOO_MANY_REQUESTS {
+            self.write_capture(capture(b"", None)).await;
+            let retry_after = response
+                .headers()
+                .get(header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| parse_retry_after(v, SystemTime::now()))
+                .unwrap_or(DEFAULT_RETRY_AFTER);
+            eprintln!("[track17-resp] rate limited, retry after {:?}", retry_after);
+            return Err(TrackError::RateLimited { retry_after }.into());
+        }
+
+        // Read incrementally so an oversized body is dropped before it's buffered
+        let limit = self.max_response_bytes;
+        if response
+            .content_length()
+            .is_some_and(|len| len > limit as u64)
+        {
+            let error = TrackError::ResponseTooLarge { limit };
+            self.write_capture(capture(b"", Some(error.to_string())))
+                .await;
+            return Err(error.into());
+        }
+        let mut body = Vec::new();
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => {
+                    self.write_capture(capture(&body, Some(e.to_string())))
+                        .await;
+                    return Err(e.into());
+                }
+            };
+            if body.len() + chunk.len() > limit {
+                // Captured up to the limit: the part that was read
+                let error = TrackError::ResponseTooLarge { limit };
+                self.write_capture(capture(&body, Some(error.to_string())))
+                    .await;
+                return Err(error.into());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        self.write_capture(capture(&body, None)).await;
+
+        // Log raw response (truncated for readability)
+        eprintln!(
+            "[track17-resp] status={}, body_len={}, body_preview={}",
+            status,
+            body.len(),
+            String::from_utf8_lossy(&body[..body.len().min(500)])
+        );
+
+        if !status.is_success() {
+            anyhow::bail!(
+                "API request failed: {} {}",
+                status,
+                String::from_utf8_lossy(&body)
+            );
+        }
+
+        serde_json::from_slice(&body)
+            .map_err(|e| anyhow::anyhow!("Failed to parse response: {}", e))
+    }
+
+    /// Save one exchange to the capture dir, if enabled. Failures are logged,
+    /// never returned: capturing must not break tracking.
+    async fn write_capture(&self, exchange: Option<serde_json::Value>) {
+        let (Some(capture), Some(mut exchange)) = (&self.capture, exchange) else {
+            return;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        exchange["captured_at_ms"] = json!(now as u64);
+        exchange["proxy"] = json!(capture.proxy);
+
+        let seq = capture.seq.fetch_add(1, Ordering::Relaxed);
+        let path = capture.dir.join(format!("track17-{}-{:04}.json", now, seq));
+        let result = async {
+            tokio::fs::create_dir_all(&capture.dir).await?;
+            tokio::fs::write(&path, serde_json::to_vec_pretty(&exchange)?).await?;
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+        if let Err(e) = result {
+            eprintln!(
+                "[track17-capture] Failed to write {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+}
+
+impl TrackTransport for HttpTransport {
+    fn request<'a>(
+        &'a self,
+        items: &'a [TrackingItem],
+        guid: &'a str,
+    ) -> BoxFuture<'a, Result<TrackingResponse>> {
+        Box::pin(self.make_request(items, guid))
+    }
+
+    fn invalidate_credentials(&self) -> BoxFuture<'_, ()> {
+        // Clears credentials and marks assets stale; the next request regenerates them
+        Box::pin(self.credential_cache.invalidate())
+    }
+
+    fn set_credentials(&self, credentials: ApiCredentials) -> BoxFuture<'_, ()> {
+        Box::pin(self.credential_cache.set_credentials(credentials))
+    }
+
+    fn warm_up(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.ensure_credentials().await.map(|_| ()) })
+    }
+}
+
+/// Check `headers` can be sent with tracking requests: valid header names and
+/// values, and none that the transport sets itself (cookie, origin, referer,
+/// last-event-id, content-type, content-length, host).
+pub fn validate_extra_headers(headers: &[(String, String)]) -> Result<()> {
+    headers
+        .iter()
+        .try_for_each(|(name, value)| check_extra_header(name, value))
+}
+
+fn check_extra_header(name: &str, value: &str) -> Result<()> {
+    let parsed = header::HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| anyhow::anyhow!("invalid header name {:?}", name))?;
+    if RESERVED_HEADERS.contains(&parsed.as_str()) {
+        anyhow::bail!(
+            "header {:?} is set by the client and can't be overridden",
+            name
+        );
+    }
+    header::HeaderValue::from_str(value)
+        .map_err(|_| anyhow::anyhow!("invalid value for header {:?}", name))?;
+    Ok(())
+}
+
+/// Headers as HAR `[{"name", "value"}]` entries
+fn har_headers<N: AsRef<str>>(headers: &[(N, String)]) -> Vec<serde_json::Value> {
+    headers
+        .iter()
+        .map(|(name, value)| json!({"name": name.as_ref(), "value": value}))
+        .collect()
+}
+
+/// Parse a `Retry-After` header value: either delay-seconds (`"120"`) or an
+/// IMF-fixdate HTTP-date (`"Sun, 06 Nov 1994 08:49:37 GMT"`).
+///
+/// Dates in the past yield a zero delay.
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Parse an IMF-fixdate (`"Sun, 06 Nov 1994 08:49:37 GMT"`).
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let date = PrimitiveDateTime::parse(
+        value,
+        format_description!(
+            "[weekday repr:short], [day] [month repr:short] [year] [hour]:[minute]:[second] GMT"
+        ),
+    )
+    .ok()?;
+    Some(date.assume_utc().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sign_source::SignSource;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct CountingSign(AtomicU32);
+
+    impl SignSource for CountingSign {
+        fn generate_sign(&self) -> BoxFuture<'_, Result<String>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Box::pin(async { Ok("warm-sign".to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_primes_credentials() {
+        let source = Arc::new(CountingSign(AtomicU32::new(0)));
+        let cache = CredentialCache::new().with_sign_source(source.clone());
+        let transport = HttpTransport::new(Client::builder().build().unwrap(), cache.clone());
+        assert!(cache.get_valid_credentials().await.is_none());
+
+        transport.warm_up().await.unwrap();
+        assert_eq!(
+            cache.get_valid_credentials().await.unwrap().sign,
+            "warm-sign"
+        );
+        assert_eq!(source.0.load(Ordering::Relaxed), 1);
+
+        // Already warm: no further generation
+        transport.warm_up().await.unwrap();
+        assert_eq!(source.0.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_locale_headers() {
+        let transport =
+            HttpTransport::new(Client::builder().build().unwrap(), CredentialCache::new())
+                .with_locale("fr", "FR");
+
+        assert_eq!(
+            transport.cookies("bid", "leid"),
+            "country=FR; _yq_bid=bid; v5_Culture=fr; Last-Event-ID=leid"
+        );
+        assert_eq!(transport.referer(), "https://t.17track.net/fr");
+        assert_eq!(transport.api_url(), "https://t.17track.net/track/restapi");
+
+        let transport = transport.with_api_base("http://127.0.0.1:8080/");
+        assert_eq!(transport.api_url(), "http://127.0.0.1:8080/track/restapi");
+        assert_eq!(transport.referer(), "http://127.0.0.1:8080/fr");
+    }
+
+    #[test]
+    fn test_extra_headers() {
+        let headers = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+        let extra = headers(&[("X-Proxy-Session", "abc123"), ("Cookie", "evil=1")]);
+
+        let err = validate_extra_headers(&extra).unwrap_err();
+        assert!(err.to_string().contains("can't be overridden"));
+        assert!(validate_extra_headers(&headers(&[("bad header", "x")])).is_err());
+        assert!(validate_extra_headers(&extra[..1]).is_ok());
+
+        // The transport keeps the valid ones and its own cookie
+        let transport =
+            HttpTransport::new(Client::builder().build().unwrap(), CredentialCache::new())
+                .with_extra_headers(&extra);
+        let sent = transport.request_headers("session=1".to_string(), String::new());
+        assert!(sent.contains(&("X-Proxy-Session", "abc123".to_string())));
+        let cookies: Vec<_> = sent
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+            .collect();
+        assert_eq!(cookies, [&("cookie", "session=1".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_retry_after_seconds() {
+        assert_eq!(
+            parse_retry_after("5", SystemTime::now()),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(parse_retry_after("soon", SystemTime::now()), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after_http_date() {
+        // Sun, 06 Nov 1994 08:49:37 GMT = 784111777
+        let now = UNIX_EPOCH + Duration::from_secs(784111772);
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now),
+            Some(Duration::from_secs(5))
+        );
+
+        // Dates in the past mean "retry now"
+        let later = UNIX_EPOCH + Duration::from_secs(784111800);
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", later),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_parse_http_date_rejects_invalid() {
+        for value in [
+            "99 Feb 2024 25:61:61 GMT",
+            "Thu, 99 Feb 2024 25:61:61 GMT",
+            "Fri, 30 Feb 2024 10:00:00 GMT",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+        ] {
+            assert_eq!(parse_http_date(value), None, "{}", value);
+        }
+        assert_eq!(
+            parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"),
+            Some(UNIX_EPOCH)
+        );
+    }
+}
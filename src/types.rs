@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use time::format_description::BorrowedFormatItem;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, UtcOffset};
 
 /// Package tracking state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,12 +18,38 @@ pub enum TrackingState {
     ExceptionHeld,
     ExceptionReturned,
     ExceptionDamaged,
+    /// Recipient refused or rejected the package
+    ExceptionRefused,
+    /// Lost or destroyed in transit
+    ExceptionLost,
+    /// The carrier has no record of the number
+    ExceptionNoRecord,
     AvailableForPickup,
     Expired,
     Unknown,
 }
 
 impl TrackingState {
+    /// Every state, in declaration order
+    pub const ALL: [TrackingState; 16] = [
+        Self::LabelCreated,
+        Self::InTransit,
+        Self::OutForDelivery,
+        Self::Delivered,
+        Self::DeliveredSigned,
+        Self::Exception,
+        Self::ExceptionDelayed,
+        Self::ExceptionHeld,
+        Self::ExceptionReturned,
+        Self::ExceptionDamaged,
+        Self::ExceptionRefused,
+        Self::ExceptionLost,
+        Self::ExceptionNoRecord,
+        Self::AvailableForPickup,
+        Self::Expired,
+        Self::Unknown,
+    ];
+
     /// Parse from 17track's stage or sub_status field
     pub fn from_stage(stage: &str) -> Self {
         match stage {
@@ -34,7 +64,11 @@ impl TrackingState {
             "Exception_Delayed" => Self::ExceptionDelayed,
             "Exception_Held" => Self::ExceptionHeld,
             "Exception_Returned" | "Exception_RTS" => Self::ExceptionReturned,
-            "Exception_Damaged" => Self::ExceptionDamaged,
+            "Exception_Damaged" | "Exception_Damage" => Self::ExceptionDamaged,
+            "Exception_Security" => Self::ExceptionHeld,
+            "Exception_Refused" | "Exception_Rejected" => Self::ExceptionRefused,
+            "Exception_Lost" | "Exception_Destroyed" => Self::ExceptionLost,
+            "Exception_NoRecord" => Self::ExceptionNoRecord,
             "AvailableForPickup" => Self::AvailableForPickup,
             "Expired" => Self::Expired,
             "Undelivered" => Self::Exception,
@@ -45,6 +79,93 @@ impl TrackingState {
             _ => Self::Unknown,
         }
     }
+
+    /// Map 17track's numeric package status (the `e` code) to a state.
+    ///
+    /// 0 not found, 10 in transit, 20 expired, 30 ready for pickup,
+    /// 35 undelivered, 40 delivered, 50 alert. "Not found" and unlisted codes
+    /// are [`Unknown`](Self::Unknown).
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            10 => Self::InTransit,
+            20 => Self::Expired,
+            30 => Self::AvailableForPickup,
+            35 | 50 => Self::Exception,
+            40 => Self::Delivered,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Journey progress as a 0-100 percentage for progress bars.
+    ///
+    /// Monotonic along the happy path (label created < in transit < out for
+    /// delivery < delivered). Exceptions, expired, and unknown states return 0.
+    pub fn progress(&self) -> u8 {
+        match self {
+            Self::LabelCreated => 10,
+            Self::InTransit => 50,
+            Self::OutForDelivery => 80,
+            Self::AvailableForPickup => 90,
+            Self::Delivered | Self::DeliveredSigned => 100,
+            Self::Exception
+            | Self::ExceptionDelayed
+            | Self::ExceptionHeld
+            | Self::ExceptionReturned
+            | Self::ExceptionDamaged
+            | Self::ExceptionRefused
+            | Self::ExceptionLost
+            | Self::ExceptionNoRecord
+            | Self::Expired
+            | Self::Unknown => 0,
+        }
+    }
+
+    /// Whether no further tracking updates are expected
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Delivered
+                | Self::DeliveredSigned
+                | Self::ExceptionReturned
+                | Self::ExceptionLost
+                | Self::Expired
+        )
+    }
+
+    /// Broad bucket UIs typically color-code by
+    pub fn category(&self) -> StateCategory {
+        match self {
+            Self::LabelCreated => StateCategory::Pending,
+            Self::InTransit | Self::OutForDelivery | Self::AvailableForPickup => {
+                StateCategory::InTransit
+            }
+            Self::Delivered | Self::DeliveredSigned => StateCategory::Delivered,
+            Self::Exception
+            | Self::ExceptionDelayed
+            | Self::ExceptionHeld
+            | Self::ExceptionReturned
+            | Self::ExceptionDamaged
+            | Self::ExceptionRefused
+            | Self::ExceptionLost
+            | Self::ExceptionNoRecord => StateCategory::Exception,
+            Self::Expired | Self::Unknown => StateCategory::Unknown,
+        }
+    }
+
+    /// Whether this is any exception state
+    pub fn is_exception(&self) -> bool {
+        matches!(
+            self,
+            Self::Exception
+                | Self::ExceptionDelayed
+                | Self::ExceptionHeld
+                | Self::ExceptionReturned
+                | Self::ExceptionDamaged
+                | Self::ExceptionRefused
+                | Self::ExceptionLost
+                | Self::ExceptionNoRecord
+        )
+    }
 }
 
 impl fmt::Display for TrackingState {
@@ -60,6 +181,9 @@ impl fmt::Display for TrackingState {
             Self::ExceptionHeld => write!(f, "EXCEPTION_HELD"),
             Self::ExceptionReturned => write!(f, "EXCEPTION_RETURNED"),
             Self::ExceptionDamaged => write!(f, "EXCEPTION_DAMAGED"),
+            Self::ExceptionRefused => write!(f, "EXCEPTION_REFUSED"),
+            Self::ExceptionLost => write!(f, "EXCEPTION_LOST"),
+            Self::ExceptionNoRecord => write!(f, "EXCEPTION_NO_RECORD"),
             Self::AvailableForPickup => write!(f, "AVAILABLE_FOR_PICKUP"),
             Self::Expired => write!(f, "EXPIRED"),
             Self::Unknown => write!(f, "UNKNOWN"),
@@ -67,7 +191,44 @@ impl fmt::Display for TrackingState {
     }
 }
 
-/// Tracking request to be base64-encoded and sent to the API
+/// Broad grouping of [`TrackingState`] (see [`TrackingState::category`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateCategory {
+    /// Label created, not yet handed to the carrier
+    Pending,
+    /// Moving, out for delivery, or waiting at a pickup point
+    InTransit,
+    Delivered,
+    /// Any exception, including returned, lost, and refused
+    Exception,
+    /// No usable status, including expired tracking
+    Unknown,
+}
+
+impl StateCategory {
+    /// Every category, in declaration order
+    pub const ALL: [StateCategory; 5] = [
+        Self::Pending,
+        Self::InTransit,
+        Self::Delivered,
+        Self::Exception,
+        Self::Unknown,
+    ];
+}
+
+impl fmt::Display for StateCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "PENDING"),
+            Self::InTransit => write!(f, "IN_TRANSIT"),
+            Self::Delivered => write!(f, "DELIVERED"),
+            Self::Exception => write!(f, "EXCEPTION"),
+            Self::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+/// Tracking request POSTed to `/track/restapi`
 #[derive(Debug, Serialize)]
 pub struct TrackingRequest {
     pub data: Vec<TrackingItem>,
@@ -75,6 +236,18 @@ pub struct TrackingRequest {
     #[serde(rename = "timeZoneOffset")]
     pub time_zone_offset: i32,
     pub sign: String,
+    /// Second language for event descriptions (e.g., `"en"` on a `fr` site).
+    /// Omitted when unset, so the default wire body is unchanged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+}
+
+impl TrackingRequest {
+    /// The exact request body: compact JSON, not base64. The Last-Event-ID
+    /// body hash is computed over these same bytes, so both must use this.
+    pub fn to_wire_body(&self) -> String {
+        serde_json::to_string(self).expect("TrackingRequest serializes to JSON")
+    }
 }
 
 /// Individual tracking item in the request
@@ -83,16 +256,134 @@ pub struct TrackingItem {
     pub num: String,
     pub fc: u32,
     pub sc: u32,
+    /// Extra carrier inputs keyed by [`ParamV2::key`] (e.g., `postal_code`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Caller's nickname for the package, echoed back in [`Shipment::tag`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
 }
 
 /// Response from the tracking API
-#[derive(Debug, Deserialize)]
+///
+/// Shipments are parsed one at a time: a malformed shipment is dropped and
+/// reported in `shipment_errors` instead of failing the whole response.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawTrackingResponse")]
 pub struct TrackingResponse {
     pub id: u32,
-    #[serde(default)]
     pub guid: String,
     pub shipments: Vec<Shipment>,
     pub meta: Meta,
+    /// Numbers still pending when the client gave up polling.
+    ///
+    /// Set by the client, never by the API. Their last partial shipment (if the
+    /// API returned one at all) stays in `shipments`.
+    pub incomplete: Vec<String>,
+    /// Shipments in the API response that failed to parse, e.g. `"NUM1: missing field `code`"`
+    pub shipment_errors: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawTrackingResponse {
+    #[serde(default, deserialize_with = "lenient_int")]
+    id: u32,
+    #[serde(default, deserialize_with = "lenient_string")]
+    guid: String,
+    #[serde(default)]
+    shipments: Vec<serde_json::Value>,
+    meta: Meta,
+    #[serde(default)]
+    incomplete: Vec<String>,
+}
+
+impl From<RawTrackingResponse> for TrackingResponse {
+    fn from(raw: RawTrackingResponse) -> Self {
+        let mut shipments = Vec::with_capacity(raw.shipments.len());
+        let mut shipment_errors = Vec::new();
+        for (index, value) in raw.shipments.into_iter().enumerate() {
+            let number = value
+                .get("number")
+                .and_then(|n| {
+                    n.as_str()
+                        .map(str::to_string)
+                        .or_else(|| n.as_u64().map(|n| n.to_string()))
+                })
+                .unwrap_or_else(|| format!("#{}", index));
+            match serde_json::from_value::<Shipment>(value) {
+                Ok(shipment) => shipments.push(shipment),
+                Err(e) => shipment_errors.push(format!("{}: {}", number, e)),
+            }
+        }
+
+        Self {
+            id: raw.id,
+            guid: raw.guid,
+            shipments,
+            meta: raw.meta,
+            incomplete: raw.incomplete,
+            shipment_errors,
+        }
+    }
+}
+
+/// An integer the API sends either as a number or as a numeric string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(i64),
+    String(String),
+}
+
+impl NumberOrString {
+    fn into_int<T: TryFrom<i64>, E: serde::de::Error>(self) -> Result<T, E> {
+        let n = match self {
+            Self::Number(n) => n,
+            Self::String(s) => s
+                .trim()
+                .parse()
+                .map_err(|_| E::custom(format!("expected an integer, got {:?}", s)))?,
+        };
+        T::try_from(n).map_err(|_| E::custom(format!("integer {} out of range", n)))
+    }
+}
+
+/// Accept `42` or `"42"`
+fn lenient_int<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: TryFrom<i64>,
+{
+    NumberOrString::deserialize(deserializer)?.into_int()
+}
+
+/// Accept `42`, `"42"` or null
+fn lenient_opt_int<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: TryFrom<i64>,
+{
+    Option::<NumberOrString>::deserialize(deserializer)?
+        .map(NumberOrString::into_int)
+        .transpose()
+}
+
+/// Accept `"42"` or `42`, keeping the digits as a string
+fn lenient_string<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(n) => n.to_string(),
+        NumberOrString::String(s) => s,
+    })
+}
+
+impl TrackingResponse {
+    /// True if every requested number resolved
+    pub fn is_complete(&self) -> bool {
+        self.incomplete.is_empty()
+    }
 }
 
 /// Extra field for code 400 responses with carrier suggestions
@@ -106,16 +397,20 @@ pub struct ShipmentExtra {
 /// Individual shipment in the response
 #[derive(Debug, Clone, Deserialize)]
 pub struct Shipment {
+    #[serde(deserialize_with = "lenient_int")]
     pub code: i32,
+    #[serde(deserialize_with = "lenient_string")]
     pub number: String,
+    #[serde(deserialize_with = "lenient_int")]
     pub carrier: u32,
+    #[serde(default, deserialize_with = "lenient_opt_int")]
     pub carrier_final: Option<u32>,
     pub param: Option<serde_json::Value>,
     pub params: Option<serde_json::Value>,
     pub params_v2: Option<Vec<ParamV2>>,
     pub extra: Option<Vec<ShipmentExtra>>,
     pub shipment: Option<ShipmentDetails>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "lenient_opt_int")]
     pub pre_status: Option<i32>,
     #[serde(default)]
     pub prior_status: Option<String>,
@@ -124,25 +419,568 @@ pub struct Shipment {
     pub state_final: Option<String>,
     pub service_type: Option<String>,
     pub service_type_final: Option<String>,
-    #[serde(default)]
+    /// 17track's key for this registration of the number.
+    ///
+    /// Stable across polling rounds of a session, and distinct for inputs that
+    /// normalize to the same number (e.g., `ab 123` and `AB123` queried with
+    /// different carriers), where `number` alone is ambiguous.
+    #[serde(default, deserialize_with = "lenient_opt_int")]
     pub key: Option<i32>,
     #[serde(default)]
     pub show_more: bool,
+    /// Nickname sent with the request ([`TrackingItem::tag`])
+    #[serde(default)]
+    pub tag: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl Shipment {
+    /// 17track's registration key, see [`key`](Self::key)
+    pub fn key(&self) -> Option<i32> {
+        self.key
+    }
+
+    /// Extra inputs the carrier asked for (empty when none are needed)
+    pub fn required_params(&self) -> &[ParamV2] {
+        self.params_v2.as_deref().unwrap_or_default()
+    }
+
+    /// Whether tracking is blocked until the caller supplies [`required_params`](Self::required_params)
+    pub fn needs_params(&self) -> bool {
+        !self.required_params().is_empty() && self.shipment.is_none()
+    }
+
+    /// Carrier actually used, preferring the auto-detected `carrier_final`
+    pub fn resolved_carrier(&self) -> u32 {
+        self.carrier_final
+            .filter(|&c| c != carriers::AUTO)
+            .unwrap_or(self.carrier)
+    }
+
+    /// Whether the latest event records a signature on delivery
+    pub fn was_signed(&self) -> bool {
+        self.shipment
+            .as_ref()
+            .and_then(|details| details.latest_event.as_ref())
+            .is_some_and(|event| event.tracking_state() == TrackingState::DeliveredSigned)
+    }
+
+    /// Current state: the latest event's or, without events, the
+    /// shipment-level [`reported_state`](Self::reported_state)
+    pub fn tracking_state(&self) -> TrackingState {
+        self.shipment
+            .as_ref()
+            .and_then(|details| details.latest_event.as_ref())
+            .map(TrackingEvent::tracking_state)
+            .or_else(|| self.reported_state())
+            .unwrap_or(TrackingState::Unknown)
+    }
+
+    /// Whether the package was delivered, per [`tracking_state`](Self::tracking_state)
+    pub fn is_delivered(&self) -> bool {
+        matches!(
+            self.tracking_state(),
+            TrackingState::Delivered | TrackingState::DeliveredSigned
+        )
+    }
+
+    /// Service type, preferring the carrier-normalized `service_type_final`
+    pub fn service_type(&self) -> Option<&str> {
+        self.service_type_final
+            .as_deref()
+            .or(self.service_type.as_deref())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Package weight/dimensions, if the carrier reported any
+    pub fn package_info(&self) -> Option<&PackageInfo> {
+        self.shipment.as_ref()?.package_info.as_ref()
+    }
+
+    /// Origin country code, if reported (see [`ShipmentDetails::origin_country`])
+    pub fn origin_country(&self) -> Option<&str> {
+        self.shipment.as_ref()?.origin_country()
+    }
+
+    /// Destination country code, if reported (see [`ShipmentDetails::destination_country`])
+    pub fn destination_country(&self) -> Option<&str> {
+        self.shipment.as_ref()?.destination_country()
+    }
+
+    /// Status 17track reported at the shipment level (`state_final`, then
+    /// `state`), independent of any events. `None` when unset or unrecognized.
+    pub fn reported_state(&self) -> Option<TrackingState> {
+        [&self.state_final, &self.state]
+            .into_iter()
+            .filter_map(|s| s.as_deref())
+            .map(TrackingState::from_stage)
+            .find(|state| *state != TrackingState::Unknown)
+    }
+
+    /// Recent status change reported by 17track, as `(from, to)`: `from` is
+    /// `prior_status`, `to` the latest event's state (or `state` without events).
+    ///
+    /// `None` when there's no prior status, either side is unrecognized, or
+    /// the two match.
+    pub fn just_transitioned(&self) -> Option<(TrackingState, TrackingState)> {
+        let from = TrackingState::from_stage(self.prior_status.as_deref()?);
+        let to = self
+            .shipment
+            .as_ref()
+            .and_then(|s| s.latest_event.as_ref())
+            .map(TrackingEvent::tracking_state)
+            .or_else(|| self.state.as_deref().map(TrackingState::from_stage))?;
+        (from != to && from != TrackingState::Unknown && to != TrackingState::Unknown)
+            .then_some((from, to))
+    }
+
+    /// Whether the newest event differs from the one `prev_fingerprint` was
+    /// taken from (see [`ShipmentDetails::latest_event_fingerprint`])
+    pub fn changed_since(&self, prev_fingerprint: Option<u64>) -> bool {
+        let current = self
+            .shipment
+            .as_ref()
+            .and_then(ShipmentDetails::latest_event_fingerprint);
+        current != prev_fingerprint
+    }
+
+    /// Carrier codes 17track suggested because auto-detection was ambiguous
+    pub fn carrier_suggestions(&self) -> impl Iterator<Item = u32> + '_ {
+        self.extra
+            .iter()
+            .flatten()
+            .flat_map(|extra| &extra.multi)
+            .copied()
+    }
+
+    /// Classify what this response says about the number, so callers can stop
+    /// polling early for inputs that will never resolve
+    pub fn outcome_hint(&self) -> OutcomeHint {
+        if self
+            .shipment
+            .as_ref()
+            .is_some_and(ShipmentDetails::has_events)
+        {
+            return OutcomeHint::Tracking;
+        }
+        match self.code {
+            100 => OutcomeHint::Registering,
+            400 if self.carrier_suggestions().next().is_some() => OutcomeHint::CarrierAmbiguous,
+            400 => OutcomeHint::InvalidFormat,
+            _ => OutcomeHint::Unknown,
+        }
+    }
+}
+
+/// What a shipment response says about its number (see [`Shipment::outcome_hint`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutcomeHint {
+    /// Newly submitted; 17track is still querying the carrier (code 100)
+    Registering,
+    /// Events are available
+    Tracking,
+    /// Auto-detection matched several carriers; retry with one of
+    /// [`Shipment::carrier_suggestions`]
+    CarrierAmbiguous,
+    /// No carrier recognizes the number (code 400, no suggestions)
+    InvalidFormat,
+    /// Nothing conclusive yet
+    Unknown,
+}
+
+impl OutcomeHint {
+    /// Whether polling again cannot change the outcome without new input
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::CarrierAmbiguous | Self::InvalidFormat)
+    }
+}
+
+/// Extra input a carrier needs before it returns tracking data
+/// (e.g., a destination postal code)
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParamV2 {
+    /// Field name to supply the value under
     pub key: String,
     pub input_type: String,
+    /// Example value to show users
     pub example: String,
+    /// Pattern the value must match
     pub regex: String,
     pub options: Vec<serde_json::Value>,
 }
 
+impl ParamV2 {
+    /// Whether `value` satisfies this parameter's regex (an invalid or empty
+    /// regex accepts anything)
+    pub fn accepts(&self, value: &str) -> bool {
+        if self.regex.is_empty() {
+            return true;
+        }
+        regex::Regex::new(&self.regex).map_or(true, |re| re.is_match(value))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct ShipmentDetails {
     pub tracking: Option<TrackingDetails>,
     pub latest_event: Option<TrackingEvent>,
+    /// Weight/dimension info, exposed by 17track under `misc_info` for some carriers
+    #[serde(default, rename = "misc_info")]
+    pub package_info: Option<PackageInfo>,
+    /// High-level journey summary (InfoReceived -> InTransit -> ... -> Delivered)
+    #[serde(default, rename = "milestone")]
+    pub milestones: Vec<Milestone>,
+    /// Origin/destination countries (`shipping_info`, or `i`/`info` in older responses)
+    #[serde(
+        default,
+        rename = "shipping_info",
+        alias = "i",
+        alias = "info",
+        deserialize_with = "lenient_route"
+    )]
+    pub route: Option<Route>,
+    /// Carrier's delivery estimate (`time_metrics.estimated_delivery_date`)
+    #[serde(
+        default,
+        rename = "time_metrics",
+        deserialize_with = "lenient_estimated_delivery"
+    )]
+    pub estimated_delivery: Option<EstimatedDelivery>,
+}
+
+impl ShipmentDetails {
+    /// Get the milestone timeline in the order 17track reported it
+    pub fn milestones(&self) -> &[Milestone] {
+        &self.milestones
+    }
+
+    /// Origin country as an ISO 3166-1 alpha-2 code when recognized (e.g., "CN")
+    pub fn origin_country(&self) -> Option<&str> {
+        self.route.as_ref()?.origin_country.as_deref()
+    }
+
+    /// Destination country as an ISO 3166-1 alpha-2 code when recognized (e.g., "US")
+    pub fn destination_country(&self) -> Option<&str> {
+        self.route.as_ref()?.destination_country.as_deref()
+    }
+
+    /// Whether the carrier has reported any events
+    pub fn has_events(&self) -> bool {
+        self.latest_event.is_some() || self.providers().iter().any(|p| !p.events.is_empty())
+    }
+
+    /// Every provider's leg, in the order 17track reports them
+    pub fn providers(&self) -> &[Provider] {
+        self.tracking
+            .as_ref()
+            .and_then(|t| t.providers.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Events from every provider merged newest first, by
+    /// [`TrackingEvent::timestamp_utc`]; events without a parseable time go last
+    pub fn events(&self) -> Vec<&TrackingEvent> {
+        let mut events: Vec<&TrackingEvent> =
+            self.providers().iter().flat_map(|p| &p.events).collect();
+        events.sort_by_cached_key(|e| std::cmp::Reverse(e.timestamp_utc()));
+        events
+    }
+
+    /// Events in `state`, newest first
+    pub fn events_with_state(&self, state: TrackingState) -> Vec<&TrackingEvent> {
+        self.events()
+            .into_iter()
+            .filter(|e| e.tracking_state() == state)
+            .collect()
+    }
+
+    /// Earliest event in `state` (e.g., when it first went out for delivery)
+    pub fn first_event_reaching(&self, state: TrackingState) -> Option<&TrackingEvent> {
+        self.events()
+            .into_iter()
+            .rev()
+            .find(|e| e.tracking_state() == state)
+    }
+
+    /// Stable hash of the newest event's time and description, for cheaply
+    /// detecting whether anything changed between polls.
+    ///
+    /// Identical data always hashes the same, across processes and releases.
+    pub fn latest_event_fingerprint(&self) -> Option<u64> {
+        let event = self
+            .latest_event
+            .as_ref()
+            .or_else(|| self.events().into_iter().next())?;
+        let time = event
+            .timestamp_rfc3339()
+            .or_else(|| event.time_iso.clone())
+            .or_else(|| event.time.clone())
+            .unwrap_or_default();
+        let description = event.description.as_deref().unwrap_or_default();
+        Some(fnv1a64(&[time.as_bytes(), b"\0", description.as_bytes()]))
+    }
+
+    /// How the first delivery event compared to the
+    /// [`estimated_delivery`](Self::estimated_delivery) window.
+    ///
+    /// `None` when there is no parseable estimate, or the delivery event has
+    /// no parseable time.
+    pub fn delivery_vs_estimate(&self) -> Option<DeliveryTiming> {
+        let (start, end) = self.estimated_delivery.as_ref()?.window_utc()?;
+        let delivered = self
+            .events()
+            .into_iter()
+            .rev()
+            .chain(self.latest_event.as_ref())
+            .find(|e| {
+                matches!(
+                    e.tracking_state(),
+                    TrackingState::Delivered | TrackingState::DeliveredSigned
+                )
+            });
+        let Some(delivered) = delivered else {
+            return Some(DeliveryTiming::NotYetDelivered);
+        };
+        let delivered_at = delivered.timestamp_utc()?;
+
+        Some(if delivered_at > end {
+            DeliveryTiming::Late((delivered_at - end).unsigned_abs())
+        } else if delivered_at < start {
+            DeliveryTiming::Early((start - delivered_at).unsigned_abs())
+        } else {
+            DeliveryTiming::OnTime
+        })
+    }
+}
+
+/// Delivery compared to the carrier's estimate, see
+/// [`ShipmentDetails::delivery_vs_estimate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryTiming {
+    /// Delivered within the estimated window
+    OnTime,
+    /// Delivered this long after the end of the window
+    Late(std::time::Duration),
+    /// Delivered this long before the start of the window
+    Early(std::time::Duration),
+    NotYetDelivered,
+}
+
+/// 64-bit FNV-1a over the concatenation of `parts`
+fn fnv1a64(parts: &[&[u8]]) -> u64 {
+    parts
+        .iter()
+        .flat_map(|part| part.iter())
+        .fold(0xcbf29ce484222325, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// Where a shipment travels from and to
+///
+/// Countries are normalized to ISO alpha-2 codes when the code or English name
+/// is recognized; otherwise the value is kept as reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "RawRoute")]
+pub struct Route {
+    pub origin_country: Option<String>,
+    pub destination_country: Option<String>,
+}
+
+/// Ignore route info in an unexpected shape rather than failing the whole shipment
+fn lenient_route<'de, D>(deserializer: D) -> Result<Option<Route>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|v| serde_json::from_value(v).ok()))
+}
+
+/// Raw route info: flat `ic`/`dc` codes or nested shipper/recipient addresses
+#[derive(Deserialize)]
+struct RawRoute {
+    #[serde(default, alias = "ic", alias = "origin")]
+    origin_country: Option<serde_json::Value>,
+    #[serde(default, alias = "dc", alias = "destination")]
+    destination_country: Option<serde_json::Value>,
+    #[serde(default)]
+    shipper_address: Option<serde_json::Value>,
+    #[serde(default)]
+    recipient_address: Option<serde_json::Value>,
+}
+
+impl From<RawRoute> for Route {
+    fn from(raw: RawRoute) -> Self {
+        fn country(
+            flat: Option<serde_json::Value>,
+            address: Option<serde_json::Value>,
+        ) -> Option<String> {
+            let flat = flat.as_ref().and_then(|v| v.as_str()).map(str::to_string);
+            let nested = address.as_ref().and_then(|a| {
+                a.get("country")
+                    .or_else(|| a.get("country_code"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            });
+            let value = flat.or(nested)?;
+            let value = value.trim();
+            if value.is_empty() {
+                return None;
+            }
+            Some(
+                crate::zipcode::country_code(value)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| value.to_string()),
+            )
+        }
+
+        Self {
+            origin_country: country(raw.origin_country, raw.shipper_address),
+            destination_country: country(raw.destination_country, raw.recipient_address),
+        }
+    }
+}
+
+/// Estimated delivery window; carriers often send a single day as `from == to`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct EstimatedDelivery {
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+impl EstimatedDelivery {
+    /// Latest promised time: the end of the window, or its start if open-ended
+    pub fn latest(&self) -> Option<&str> {
+        self.to
+            .as_deref()
+            .or(self.from.as_deref())
+            .filter(|t| !t.trim().is_empty())
+    }
+
+    /// The window as UTC times, parsed like event times. A date without a
+    /// time (`"2024-01-15"`) covers that whole day.
+    pub fn window_utc(&self) -> Option<(OffsetDateTime, OffsetDateTime)> {
+        let from = self.from.as_deref().filter(|t| !t.trim().is_empty());
+        let to = self.to.as_deref().filter(|t| !t.trim().is_empty());
+        let start = parse_estimate_time(from.or(to)?, false)?;
+        let end = parse_estimate_time(to.or(from)?, true)?;
+        Some((start, end.max(start)))
+    }
+}
+
+/// [`parse_event_time`], also accepting a bare date as the start of that day
+/// or, with `end_of_day`, the start of the next
+fn parse_estimate_time(value: &str, end_of_day: bool) -> Option<OffsetDateTime> {
+    parse_event_time(value).or_else(|| {
+        let day = Date::parse(value.trim(), format_description!("[year]-[month]-[day]")).ok()?;
+        let start = day.midnight().assume_utc();
+        Some(if end_of_day {
+            start + time::Duration::DAY
+        } else {
+            start
+        })
+    })
+}
+
+/// Pull `estimated_delivery_date` out of `time_metrics`, ignoring unexpected shapes
+fn lenient_estimated_delivery<'de, D>(
+    deserializer: D,
+) -> Result<Option<EstimatedDelivery>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(value
+        .and_then(|mut v| {
+            v.get_mut("estimated_delivery_date")
+                .map(serde_json::Value::take)
+        })
+        .and_then(|v| serde_json::from_value::<EstimatedDelivery>(v).ok())
+        .filter(|eta| eta.latest().is_some()))
+}
+
+/// A single high-level stage in the shipment's journey
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "RawMilestone")]
+pub struct Milestone {
+    pub state: TrackingState,
+    pub time: Option<String>,
+}
+
+/// Raw milestone entry as sent by 17track
+#[derive(Deserialize)]
+struct RawMilestone {
+    #[serde(default)]
+    key_stage: Option<String>,
+    #[serde(default)]
+    time_iso: Option<String>,
+    #[serde(default)]
+    time_utc: Option<String>,
+}
+
+impl From<RawMilestone> for Milestone {
+    fn from(raw: RawMilestone) -> Self {
+        Self {
+            state: raw
+                .key_stage
+                .as_deref()
+                .map(TrackingState::from_stage)
+                .unwrap_or(TrackingState::Unknown),
+            time: raw.time_iso.or(raw.time_utc).filter(|t| !t.is_empty()),
+        }
+    }
+}
+
+/// Structured package weight and dimensions
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(from = "RawPackageInfo")]
+pub struct PackageInfo {
+    pub weight: Option<f64>,
+    pub weight_unit: Option<String>,
+    pub dimensions: Option<String>,
+}
+
+/// Raw `misc_info` object as sent by 17track
+#[derive(Deserialize)]
+struct RawPackageInfo {
+    #[serde(default)]
+    weight_raw: Option<String>,
+    #[serde(default)]
+    weight_kg: Option<String>,
+    #[serde(default)]
+    dimensions: Option<String>,
+}
+
+impl From<RawPackageInfo> for PackageInfo {
+    fn from(raw: RawPackageInfo) -> Self {
+        // Prefer the carrier's raw weight ("1.2 kg", "3 lbs"), fall back to weight_kg
+        let (weight, weight_unit) = match raw.weight_raw.as_deref().and_then(parse_weight) {
+            Some((value, unit)) => (Some(value), unit),
+            None => match raw.weight_kg.as_deref().and_then(parse_weight) {
+                Some((value, unit)) => (Some(value), unit.or_else(|| Some("kg".to_string()))),
+                None => (None, None),
+            },
+        };
+
+        Self {
+            weight,
+            weight_unit,
+            dimensions: raw.dimensions.filter(|d| !d.trim().is_empty()),
+        }
+    }
+}
+
+/// Parse a weight string like "1.2 kg" or "3lbs" into a value and optional unit
+fn parse_weight(raw: &str) -> Option<(f64, Option<String>)> {
+    let raw = raw.trim();
+    let split = raw
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(raw.len());
+    let value: f64 = raw[..split].replace(',', ".").parse().ok()?;
+    let unit = raw[split..].trim();
+    let unit = (!unit.is_empty()).then(|| unit.to_lowercase());
+    Some((value, unit))
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -150,11 +988,48 @@ pub struct TrackingDetails {
     pub providers: Option<Vec<Provider>>,
 }
 
+/// One carrier's leg of the journey, e.g. the origin carrier before handoff
+/// to the destination carrier
 #[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawProvider")]
 pub struct Provider {
+    /// 17track carrier code (see [`carriers`])
+    pub carrier: Option<u32>,
+    /// Carrier name as reported (e.g., "Cainiao")
+    pub name: Option<String>,
     pub events: Vec<TrackingEvent>,
 }
 
+/// Raw provider entry: carrier info nested under `provider`
+#[derive(Deserialize)]
+struct RawProvider {
+    #[serde(default)]
+    provider: Option<RawProviderInfo>,
+    #[serde(default)]
+    events: Vec<TrackingEvent>,
+}
+
+#[derive(Deserialize)]
+struct RawProviderInfo {
+    #[serde(default, deserialize_with = "lenient_opt_int")]
+    key: Option<u32>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+impl From<RawProvider> for Provider {
+    fn from(raw: RawProvider) -> Self {
+        let (carrier, name) = raw
+            .provider
+            .map_or((None, None), |info| (info.key, info.name));
+        Self {
+            carrier,
+            name: name.filter(|n| !n.trim().is_empty()),
+            events: raw.events,
+        }
+    }
+}
+
 /// Location can be either a string or a structured object
 #[derive(Debug, Clone, Deserialize)]
 #[serde(untagged)]
@@ -167,6 +1042,8 @@ pub enum LocationData {
 pub struct LocationDetails {
     pub city: Option<String>,
     pub state: Option<String>,
+    /// Country code or name; `{"code", "name"}` objects are reduced to the code
+    #[serde(default, deserialize_with = "lenient_country")]
     pub country: Option<String>,
     pub postal_code: Option<String>,
     pub zip_code: Option<String>,
@@ -178,40 +1055,172 @@ pub struct LocationDetails {
     pub postal_code_alt: Option<String>,
 }
 
+impl LocationDetails {
+    /// Country as reported (`country`, then `country_code`), if any
+    pub fn country(&self) -> Option<&str> {
+        self.country
+            .as_deref()
+            .or(self.country_code.as_deref())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+}
+
+/// Accept `"CA"` or `{"code": "CA", "name": "Canada"}`, preferring the code
+fn lenient_country<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        Some(serde_json::Value::String(s)) => Some(s),
+        Some(serde_json::Value::Object(map)) => ["code", "country_code", "name"]
+            .iter()
+            .find_map(|key| map.get(*key)?.as_str())
+            .map(str::to_string),
+        _ => None,
+    })
+}
+
 #[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawTrackingEvent")]
 pub struct TrackingEvent {
     pub time: Option<String>,
     pub time_iso: Option<String>,
     pub time_utc: Option<String>,
+    /// Description in the requested language (the site locale, or the
+    /// translation language when one was requested)
     pub description: Option<String>,
+    /// The carrier's untranslated description, when the API sent a
+    /// translation alongside it
+    pub description_original: Option<String>,
     pub location: Option<LocationData>,
     pub stage: Option<String>,
     pub sub_status: Option<String>,
+    /// Numeric package status (`e`), see [`TrackingState::from_code`]
+    pub stage_code: Option<i32>,
+}
+
+/// Raw event: a translated description arrives as
+/// `description_translation: {"lang", "description"}` next to the original
+#[derive(Deserialize)]
+struct RawTrackingEvent {
+    time: Option<String>,
+    time_iso: Option<String>,
+    time_utc: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    description_original: Option<String>,
+    #[serde(default)]
+    description_translation: Option<RawDescriptionTranslation>,
+    location: Option<LocationData>,
+    stage: Option<String>,
+    sub_status: Option<String>,
+    #[serde(default, rename = "e", deserialize_with = "lenient_opt_int")]
+    stage_code: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct RawDescriptionTranslation {
+    #[serde(default)]
+    description: Option<String>,
+}
+
+impl From<RawTrackingEvent> for TrackingEvent {
+    fn from(raw: RawTrackingEvent) -> Self {
+        let translated = raw
+            .description_translation
+            .and_then(|t| t.description)
+            .filter(|d| !d.trim().is_empty());
+        let (description, description_original) = match translated {
+            Some(translated) => (
+                Some(translated),
+                raw.description_original.or(raw.description),
+            ),
+            None => (raw.description, raw.description_original),
+        };
+
+        Self {
+            time: raw.time,
+            time_iso: raw.time_iso,
+            time_utc: raw.time_utc,
+            description,
+            description_original,
+            location: raw.location,
+            stage: raw.stage,
+            sub_status: raw.sub_status,
+            stage_code: raw.stage_code,
+        }
+    }
 }
 
 impl TrackingEvent {
-    /// Get the tracking state from this event's stage or sub_status
+    /// Get the tracking state from this event's stage or sub_status, or its
+    /// numeric `stage_code` when neither is present
     pub fn tracking_state(&self) -> TrackingState {
-        // Try stage first, then fall back to sub_status
-        self.stage
+        // A recognized sub_status refines the stage (stage "Delivered" +
+        // sub_status "Delivered_Signed"), so it wins; otherwise use the stage
+        let sub_status = self
+            .sub_status
             .as_deref()
-            .or(self.sub_status.as_deref())
             .map(TrackingState::from_stage)
+            .filter(|state| *state != TrackingState::Unknown);
+        sub_status
+            .or(self.stage.as_deref().map(TrackingState::from_stage))
+            .or(self.stage_code.map(TrackingState::from_code))
             .unwrap_or(TrackingState::Unknown)
     }
 
+    /// Why the package is in an exception state, if it is.
+    ///
+    /// Prefers the carrier's description (e.g., "Recipient refused delivery"),
+    /// falling back to the `sub_status` code (e.g., `Exception_Refused`).
+    pub fn exception_reason(&self) -> Option<&str> {
+        if !self.tracking_state().is_exception() {
+            return None;
+        }
+        self.description
+            .as_deref()
+            .filter(|d| !d.trim().is_empty())
+            .or(self.sub_status.as_deref())
+    }
+
+    /// When this event happened, in UTC.
+    ///
+    /// Uses the first field that parses, preferring `time_utc`, then
+    /// `time_iso`, then `time`. Carrier-local formats without an offset
+    /// (`"2024-01-02 13:45:00"`) are taken as UTC.
+    pub fn timestamp_utc(&self) -> Option<OffsetDateTime> {
+        [&self.time_utc, &self.time_iso, &self.time]
+            .into_iter()
+            .filter_map(|t| t.as_deref())
+            .find_map(parse_event_time)
+    }
+
+    /// [`timestamp_utc`](Self::timestamp_utc) as RFC 3339 (`"2024-01-02T13:45:00Z"`)
+    pub fn timestamp_rfc3339(&self) -> Option<String> {
+        self.timestamp_utc()?.format(&Rfc3339).ok()
+    }
+
     /// Get the raw location string
     pub fn raw_location(&self) -> Option<String> {
+        self.location_string(false)
+    }
+
+    /// [`raw_location`](Self::raw_location) with the country appended when a
+    /// structured location has one that isn't already shown, so "Toronto, ON"
+    /// and "Austin, TX" become "Toronto, ON, CA" and "Austin, TX, US".
+    pub fn raw_location_with_country(&self) -> Option<String> {
+        self.location_string(true)
+    }
+
+    fn location_string(&self, with_country: bool) -> Option<String> {
         match &self.location {
             Some(LocationData::String(s)) if !s.is_empty() => Some(s.clone()),
             Some(LocationData::Structured(loc)) => {
                 let city = loc.city.as_deref().filter(|s| !s.is_empty());
                 let state = loc.state.as_deref().filter(|s| !s.is_empty());
-                let country = loc
-                    .country
-                    .as_deref()
-                    .or(loc.country_code.as_deref())
-                    .filter(|s| !s.is_empty());
+                let country = loc.country();
                 let postal = loc
                     .postal_code
                     .as_deref()
@@ -219,17 +1228,24 @@ impl TrackingEvent {
                     .or(loc.zip_code.as_deref())
                     .filter(|s| !s.is_empty());
 
-                match (city, state, postal) {
-                    (Some(c), Some(s), _) => Some(format!("{}, {}", c, s)),
-                    (Some(c), None, Some(p)) => Some(format!("{} {}", c, p)),
-                    (Some(c), None, None) => Some(c.to_string()),
-                    (None, Some(s), Some(p)) => Some(format!("{} {}", s, p)),
-                    (None, Some(s), None) => Some(s.to_string()),
-                    (None, None, Some(p)) => match country {
-                        Some(co) => Some(format!("{} {}", co, p)),
-                        None => Some(p.to_string()),
-                    },
-                    _ => loc.address.clone(),
+                let location = match (city, state, postal) {
+                    (Some(c), Some(s), _) => format!("{}, {}", c, s),
+                    (Some(c), None, Some(p)) => format!("{} {}", c, p),
+                    (Some(c), None, None) => c.to_string(),
+                    (None, Some(s), Some(p)) => format!("{} {}", s, p),
+                    (None, Some(s), None) => s.to_string(),
+                    // Already "{country} {postal}"
+                    (None, None, Some(p)) => {
+                        return Some(match country {
+                            Some(co) => format!("{} {}", co, p),
+                            None => p.to_string(),
+                        });
+                    }
+                    _ => return loc.address.clone(),
+                };
+                match country {
+                    Some(co) if with_country => Some(format!("{}, {}", location, co)),
+                    _ => Some(location),
                 }
             }
             _ => None,
@@ -248,18 +1264,1121 @@ impl TrackingEvent {
     }
 }
 
+/// Offset-less timestamp formats seen across carriers
+const CARRIER_TIME_FORMATS: &[&[BorrowedFormatItem<'static>]] = &[
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second][optional [.[subsecond]]]"),
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+    format_description!("[year]-[month]-[day] [hour]:[minute]"),
+    format_description!("[year]/[month]/[day] [hour]:[minute]:[second]"),
+    format_description!("[year]/[month]/[day] [hour]:[minute]"),
+];
+
+fn parse_event_time(value: &str) -> Option<OffsetDateTime> {
+    let value = value.trim();
+    if let Ok(time) = OffsetDateTime::parse(value, &Rfc3339) {
+        return Some(time.to_offset(UtcOffset::UTC));
+    }
+    CARRIER_TIME_FORMATS
+        .iter()
+        .find_map(|format| PrimitiveDateTime::parse(value, format).ok())
+        .map(PrimitiveDateTime::assume_utc)
+}
+
 /// Metadata in the response
 #[derive(Debug, Clone, Deserialize)]
 pub struct Meta {
+    #[serde(deserialize_with = "lenient_int")]
     pub code: i32,
+    #[serde(default)]
     pub message: String,
 }
 
+impl Meta {
+    /// How the client should treat this response.
+    ///
+    /// A message mentioning uIP is treated as [`MetaCode::InvalidUip`] whatever
+    /// the code, since 17track doesn't always send -5 with it.
+    pub fn classify(&self) -> MetaCode {
+        let code = MetaCode::from_code(self.code);
+        if !code.rejects_credentials() && self.message.to_lowercase().contains("uip") {
+            return MetaCode::InvalidUip;
+        }
+        code
+    }
+}
+
+/// Known [`Meta::code`] values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetaCode {
+    /// 200: shipments are usable
+    Ok,
+    /// -11: the sign was rejected
+    InvalidSign,
+    /// -14: the session cookie expired (empty shipments, empty guid)
+    InvalidSession,
+    /// -5: IP-based rate limiting (uIP)
+    InvalidUip,
+    /// 429: rate limited, reported in the body instead of the HTTP status
+    RateLimited,
+    /// 503: 17track is down for maintenance
+    Maintenance,
+    /// Any other code, preserved as sent
+    Unknown(i32),
+}
+
+impl MetaCode {
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            200 => Self::Ok,
+            -11 => Self::InvalidSign,
+            -14 => Self::InvalidSession,
+            -5 => Self::InvalidUip,
+            429 => Self::RateLimited,
+            503 => Self::Maintenance,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// Numeric code as sent by the API
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Ok => 200,
+            Self::InvalidSign => -11,
+            Self::InvalidSession => -14,
+            Self::InvalidUip => -5,
+            Self::RateLimited => 429,
+            Self::Maintenance => 503,
+            Self::Unknown(code) => code,
+        }
+    }
+
+    /// Whether the API rejected the sign or session, so fresh credentials
+    /// may fix it
+    pub fn rejects_credentials(self) -> bool {
+        matches!(
+            self,
+            Self::InvalidSign | Self::InvalidSession | Self::InvalidUip
+        )
+    }
+}
+
 /// Carrier codes
 pub mod carriers {
+    use std::sync::LazyLock;
+
+    use regex::Regex;
+
     pub const AUTO: u32 = 0; // Auto-detect carrier
     pub const FEDEX: u32 = 100003;
     pub const UPS: u32 = 100001;
     pub const USPS: u32 = 100002;
     pub const DHL: u32 = 100005;
+
+    /// Resolve a carrier name (case-insensitive) to its code
+    pub fn carrier_from_name(name: &str) -> Option<u32> {
+        match name.trim().to_lowercase().as_str() {
+            "auto" => Some(AUTO),
+            "fedex" => Some(FEDEX),
+            "ups" => Some(UPS),
+            "usps" => Some(USPS),
+            "dhl" => Some(DHL),
+            _ => None,
+        }
+    }
+
+    /// Tracking number formats per carrier: (carrier, pattern, check digit)
+    const FORMATS: &[(u32, &str, fn(&str) -> bool)] = &[
+        (UPS, r"^1Z[0-9A-Z]{16}$", ups_check_digit),
+        (FEDEX, r"^[0-9]{12}$", fedex_check_digit),
+        (FEDEX, r"^[0-9]{15}$|^[0-9]{20}$|^[0-9]{22}$", |_| true),
+        (USPS, r"^9[1-5][0-9]{18,20}$", usps_check_digit),
+        (USPS, r"^[A-Z]{2}[0-9]{9}US$", |_| true),
+        (
+            DHL,
+            r"^[0-9]{10}$|^JJD[0-9]{18,20}$|^JVGL[0-9]{16,20}$",
+            |_| true,
+        ),
+    ];
+
+    /// [`FORMATS`] with the patterns compiled once; a bad pattern panics on
+    /// first use rather than silently never matching
+    static COMPILED_FORMATS: LazyLock<Vec<(u32, Regex, fn(&str) -> bool)>> = LazyLock::new(|| {
+        FORMATS
+            .iter()
+            .map(|&(code, pattern, check)| {
+                let re = Regex::new(pattern)
+                    .unwrap_or_else(|e| panic!("invalid format for carrier {}: {}", code, e));
+                (code, re, check)
+            })
+            .collect()
+    });
+
+    /// Number as 17track echoes it back: uppercase, without whitespace
+    pub fn normalize(number: &str) -> String {
+        number
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_uppercase()
+    }
+
+    fn matches_format(number: &str, carrier: u32) -> bool {
+        COMPILED_FORMATS
+            .iter()
+            .any(|(code, re, check)| *code == carrier && re.is_match(number) && check(number))
+    }
+
+    /// Check whether `number` is well-formed for `carrier`.
+    ///
+    /// Verifies the known format and, where the carrier defines one, the check
+    /// digit. `AUTO` and carriers without a known format always pass; 17track
+    /// supports far more carriers than can be validated locally.
+    pub fn validate(number: &str, carrier: u32) -> bool {
+        if !FORMATS.iter().any(|&(code, _, _)| code == carrier) {
+            return true;
+        }
+        matches_format(&normalize(number), carrier)
+    }
+
+    /// Guess candidate carriers from the number's format (may be empty or ambiguous).
+    pub fn detect(number: &str) -> Vec<u32> {
+        let number = normalize(number);
+        let mut candidates = Vec::new();
+        for &(code, _, _) in FORMATS {
+            if !candidates.contains(&code) && matches_format(&number, code) {
+                candidates.push(code);
+            }
+        }
+        candidates
+    }
+
+    /// UPS: letters map to digits, odd positions weigh 1 and even positions 2
+    fn ups_check_digit(number: &str) -> bool {
+        let values: Vec<u32> = number[2..]
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(d) => d,
+                None => (c as u32 - 'A' as u32 + 2) % 10,
+            })
+            .collect();
+        let (check, body) = values.split_last().unwrap();
+        let sum: u32 = body
+            .iter()
+            .enumerate()
+            .map(|(i, v)| if i % 2 == 1 { v * 2 } else { *v })
+            .sum();
+        (10 - sum % 10) % 10 == *check
+    }
+
+    /// FedEx Express (12 digits): weights 1, 3, 7 from the right, mod 11
+    fn fedex_check_digit(number: &str) -> bool {
+        let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+        let (check, body) = digits.split_last().unwrap();
+        let sum: u32 = body
+            .iter()
+            .rev()
+            .zip([1, 3, 7].iter().cycle())
+            .map(|(d, w)| d * w)
+            .sum();
+        sum % 11 % 10 == *check
+    }
+
+    /// USPS IMpb: weights 3, 1 from the right, mod 10
+    fn usps_check_digit(number: &str) -> bool {
+        let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+        let (check, body) = digits.split_last().unwrap();
+        let sum: u32 = body
+            .iter()
+            .rev()
+            .zip([3, 1].iter().cycle())
+            .map(|(d, w)| d * w)
+            .sum();
+        (10 - sum % 10) % 10 == *check
+    }
+
+    /// Display name for a known carrier code
+    pub fn carrier_name(code: u32) -> Option<&'static str> {
+        match code {
+            FEDEX => Some("FedEx"),
+            UPS => Some("UPS"),
+            USPS => Some("USPS"),
+            DHL => Some("DHL"),
+            _ => None,
+        }
+    }
+
+    /// Public tracking page for `number`, on the carrier's own site when known
+    /// and on 17track otherwise. `None` for a blank number.
+    pub fn tracking_url(carrier: u32, number: &str) -> Option<String> {
+        let number = number.trim();
+        if number.is_empty() {
+            return None;
+        }
+        let number = encode_query_value(number);
+        Some(match carrier {
+            FEDEX => format!("https://www.fedex.com/fedextrack/?trknbr={}", number),
+            UPS => format!("https://www.ups.com/track?tracknum={}", number),
+            USPS => format!(
+                "https://tools.usps.com/go/TrackConfirmAction?tLabels={}",
+                number
+            ),
+            DHL => format!(
+                "https://www.dhl.com/global-en/home/tracking/tracking-express.html?submit=1&tracking-id={}",
+                number
+            ),
+            _ => format!("https://t.17track.net/en#nums={}", number),
+        })
+    }
+
+    /// Percent-encode everything but ASCII alphanumerics and `-`
+    fn encode_query_value(value: &str) -> String {
+        value
+            .bytes()
+            .map(|b| match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' => (b as char).to_string(),
+                _ => format!("%{:02X}", b),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_ordering() {
+        assert!(TrackingState::LabelCreated.progress() < TrackingState::InTransit.progress());
+        assert!(TrackingState::InTransit.progress() < TrackingState::OutForDelivery.progress());
+        assert!(TrackingState::OutForDelivery.progress() < TrackingState::Delivered.progress());
+        assert_eq!(TrackingState::DeliveredSigned.progress(), 100);
+        assert_eq!(TrackingState::ExceptionHeld.progress(), 0);
+    }
+
+    #[test]
+    fn test_raw_location_with_country() {
+        let event = |location: serde_json::Value| -> TrackingEvent {
+            serde_json::from_value(serde_json::json!({"location": location})).unwrap()
+        };
+
+        let toronto = event(serde_json::json!({
+            "city": "Toronto",
+            "state": "ON",
+            "country": {"code": "CA", "name": "Canada"}
+        }));
+        assert_eq!(toronto.raw_location().as_deref(), Some("Toronto, ON"));
+        assert_eq!(
+            toronto.raw_location_with_country().as_deref(),
+            Some("Toronto, ON, CA")
+        );
+
+        let austin = event(serde_json::json!({"city": "Austin", "state": "TX", "country": "US"}));
+        assert_eq!(austin.raw_location().as_deref(), Some("Austin, TX"));
+        assert_eq!(
+            austin.raw_location_with_country().as_deref(),
+            Some("Austin, TX, US")
+        );
+
+        // Country already leads postal-only locations; strings pass through
+        let postal = event(serde_json::json!({"postal_code": "60455", "countryCode": "US"}));
+        assert_eq!(
+            postal.raw_location_with_country().as_deref(),
+            Some("US 60455")
+        );
+        let plain = event(serde_json::json!("Chicago, IL"));
+        assert_eq!(
+            plain.raw_location_with_country().as_deref(),
+            Some("Chicago, IL")
+        );
+    }
+
+    #[test]
+    fn test_is_terminal() {
+        assert!(TrackingState::Delivered.is_terminal());
+        assert!(TrackingState::DeliveredSigned.is_terminal());
+        assert!(TrackingState::ExceptionReturned.is_terminal());
+        assert!(TrackingState::Expired.is_terminal());
+        assert!(!TrackingState::InTransit.is_terminal());
+        assert!(!TrackingState::Exception.is_terminal());
+        assert!(!TrackingState::Unknown.is_terminal());
+    }
+
+    #[test]
+    fn test_category() {
+        for state in TrackingState::ALL {
+            let expected = match state {
+                TrackingState::LabelCreated => StateCategory::Pending,
+                TrackingState::InTransit
+                | TrackingState::OutForDelivery
+                | TrackingState::AvailableForPickup => StateCategory::InTransit,
+                TrackingState::Delivered | TrackingState::DeliveredSigned => {
+                    StateCategory::Delivered
+                }
+                s if s.is_exception() => StateCategory::Exception,
+                _ => StateCategory::Unknown,
+            };
+            assert_eq!(state.category(), expected, "{}", state);
+        }
+        assert_eq!(TrackingState::Expired.category(), StateCategory::Unknown);
+        assert_eq!(
+            TrackingState::ExceptionLost.category(),
+            StateCategory::Exception
+        );
+    }
+
+    #[test]
+    fn test_exception_sub_statuses() {
+        let cases = [
+            ("Exception_Refused", TrackingState::ExceptionRefused),
+            ("Exception_Rejected", TrackingState::ExceptionRefused),
+            ("Exception_Lost", TrackingState::ExceptionLost),
+            ("Exception_Destroyed", TrackingState::ExceptionLost),
+            ("Exception_NoRecord", TrackingState::ExceptionNoRecord),
+            ("Exception_Damage", TrackingState::ExceptionDamaged),
+            ("Exception_Security", TrackingState::ExceptionHeld),
+            ("Exception_Other", TrackingState::Exception),
+        ];
+        for (stage, expected) in cases {
+            assert_eq!(TrackingState::from_stage(stage), expected, "{}", stage);
+            assert!(expected.is_exception());
+        }
+        assert!(TrackingState::ExceptionLost.is_terminal());
+        assert!(!TrackingState::ExceptionRefused.is_terminal());
+        assert!(!TrackingState::Delivered.is_exception());
+    }
+
+    #[test]
+    fn test_numeric_stage_codes() {
+        let cases = [
+            (0, TrackingState::Unknown),
+            (10, TrackingState::InTransit),
+            (20, TrackingState::Expired),
+            (30, TrackingState::AvailableForPickup),
+            (35, TrackingState::Exception),
+            (40, TrackingState::Delivered),
+            (50, TrackingState::Exception),
+            (99, TrackingState::Unknown),
+        ];
+        for (code, expected) in cases {
+            assert_eq!(TrackingState::from_code(code), expected, "{}", code);
+        }
+
+        let event = |json| serde_json::from_value::<TrackingEvent>(json).unwrap();
+        let numeric = event(serde_json::json!({"description": "Delivered", "e": "40"}));
+        assert_eq!(numeric.stage_code, Some(40));
+        assert_eq!(numeric.tracking_state(), TrackingState::Delivered);
+        // The string stage wins when both are present
+        let both = event(serde_json::json!({"stage": "InTransit", "e": 40}));
+        assert_eq!(both.tracking_state(), TrackingState::InTransit);
+    }
+
+    #[test]
+    fn test_signed_delivery() {
+        let shipment = |stage: &str, sub_status: &str| -> Shipment {
+            serde_json::from_value(serde_json::json!({
+                "code": 200,
+                "number": "NUM1",
+                "carrier": 100002,
+                "shipment": {
+                    "latest_event": {"description": "Delivered", "stage": stage, "sub_status": sub_status}
+                }
+            }))
+            .unwrap()
+        };
+
+        let signed = shipment("Delivered", "Delivered_Signed");
+        let event = signed
+            .shipment
+            .as_ref()
+            .unwrap()
+            .latest_event
+            .as_ref()
+            .unwrap();
+        assert_eq!(event.tracking_state(), TrackingState::DeliveredSigned);
+        assert_eq!(event.tracking_state().to_string(), "DELIVERED_SIGNED");
+        assert!(signed.was_signed());
+
+        let other = shipment("Delivered", "Delivered_Other");
+        let event = other
+            .shipment
+            .as_ref()
+            .unwrap()
+            .latest_event
+            .as_ref()
+            .unwrap();
+        assert_eq!(event.tracking_state(), TrackingState::Delivered);
+        assert!(!other.was_signed());
+
+        // Unrecognized sub-statuses don't override the stage
+        let unknown = shipment("Delivered", "Something_New");
+        let event = unknown
+            .shipment
+            .as_ref()
+            .unwrap()
+            .latest_event
+            .as_ref()
+            .unwrap();
+        assert_eq!(event.tracking_state(), TrackingState::Delivered);
+    }
+
+    #[test]
+    fn test_exception_reason() {
+        let event: TrackingEvent = serde_json::from_value(serde_json::json!({
+            "description": "Recipient refused delivery",
+            "sub_status": "Exception_Refused"
+        }))
+        .unwrap();
+        assert_eq!(event.tracking_state(), TrackingState::ExceptionRefused);
+        assert_eq!(event.exception_reason(), Some("Recipient refused delivery"));
+
+        // No description: the sub-status code is the best we have
+        let event: TrackingEvent =
+            serde_json::from_value(serde_json::json!({"sub_status": "Exception_Lost"})).unwrap();
+        assert_eq!(event.exception_reason(), Some("Exception_Lost"));
+
+        let event: TrackingEvent = serde_json::from_value(serde_json::json!({
+            "description": "Delivered",
+            "stage": "Delivered"
+        }))
+        .unwrap();
+        assert_eq!(event.exception_reason(), None);
+    }
+
+    #[test]
+    fn test_event_timestamp_normalized() {
+        let event = |json| serde_json::from_value::<TrackingEvent>(json).unwrap();
+
+        // RFC 3339 with an offset is converted to UTC
+        let e = event(serde_json::json!({"time_iso": "2024-01-10T08:00:00-05:00"}));
+        assert_eq!(
+            e.timestamp_rfc3339().as_deref(),
+            Some("2024-01-10T13:00:00Z")
+        );
+
+        // time_utc wins over time_iso and time
+        let e = event(serde_json::json!({
+            "time_utc": "2024-01-10T13:00:00Z",
+            "time_iso": "2024-01-10T09:00:00-04:00",
+            "time": "2024-01-10 09:00"
+        }));
+        assert_eq!(
+            e.timestamp_rfc3339().as_deref(),
+            Some("2024-01-10T13:00:00Z")
+        );
+
+        // Carrier-local format without an offset
+        let e = event(serde_json::json!({"time": "2024-01-02 13:45:00"}));
+        assert_eq!(
+            e.timestamp_utc().map(|t| t.unix_timestamp()),
+            Some(1_704_203_100)
+        );
+        assert_eq!(
+            e.timestamp_rfc3339().as_deref(),
+            Some("2024-01-02T13:45:00Z")
+        );
+
+        // Unparseable fields fall through to the next one
+        let e = event(serde_json::json!({"time_iso": "", "time": "not a date"}));
+        assert_eq!(e.timestamp_utc(), None);
+    }
+
+    #[test]
+    fn test_package_info_weight() {
+        let json = r#"{
+            "code": 200,
+            "number": "123456789012",
+            "carrier": 100003,
+            "carrier_final": null,
+            "param": null,
+            "params": null,
+            "params_v2": null,
+            "extra": null,
+            "shipment": {
+                "tracking": null,
+                "latest_event": null,
+                "misc_info": {"weight_raw": "1.2 kg", "dimensions": "10x20x30 cm"}
+            },
+            "state_final": null,
+            "service_type": "Ground",
+            "service_type_final": "FedEx Ground"
+        }"#;
+        let shipment: Shipment = serde_json::from_str(json).unwrap();
+
+        let info = shipment.package_info().unwrap();
+        assert_eq!(info.weight, Some(1.2));
+        assert_eq!(info.weight_unit.as_deref(), Some("kg"));
+        assert_eq!(info.dimensions.as_deref(), Some("10x20x30 cm"));
+        assert_eq!(shipment.service_type(), Some("FedEx Ground"));
+    }
+
+    #[test]
+    fn test_resolved_carrier() {
+        let json = r#"{
+            "code": 200,
+            "number": "123456789012",
+            "carrier": 0,
+            "carrier_final": 100003,
+            "param": null,
+            "params": null,
+            "params_v2": null,
+            "extra": null,
+            "shipment": null,
+            "state_final": null,
+            "service_type": null,
+            "service_type_final": null
+        }"#;
+        let mut shipment: Shipment = serde_json::from_str(json).unwrap();
+        assert_eq!(shipment.resolved_carrier(), carriers::FEDEX);
+        assert_eq!(
+            carriers::carrier_name(shipment.resolved_carrier()),
+            Some("FedEx")
+        );
+
+        // A zero or missing carrier_final falls back to the requested carrier
+        shipment.carrier = carriers::UPS;
+        shipment.carrier_final = Some(0);
+        assert_eq!(shipment.resolved_carrier(), carriers::UPS);
+        shipment.carrier_final = None;
+        assert_eq!(shipment.resolved_carrier(), carriers::UPS);
+    }
+
+    #[test]
+    fn test_validate_tracking_numbers() {
+        assert!(carriers::validate("1Z999AA10123456784", carriers::UPS));
+        assert!(carriers::validate("1z 999 aa1 0123456784", carriers::UPS));
+        // Wrong check digit
+        assert!(!carriers::validate("1Z999AA10123456785", carriers::UPS));
+        assert!(!carriers::validate("not-a-number", carriers::UPS));
+        assert!(!carriers::validate("not-a-number", carriers::FEDEX));
+        assert!(carriers::validate("9400111899223197428497", carriers::USPS));
+        assert!(!carriers::validate(
+            "9400111899223197428490",
+            carriers::USPS
+        ));
+        assert!(carriers::validate("797843158299", carriers::FEDEX));
+        // Nothing to check against for auto-detect
+        assert!(carriers::validate("not-a-number", carriers::AUTO));
+    }
+
+    #[test]
+    fn test_tracking_url() {
+        assert_eq!(
+            carriers::tracking_url(carriers::FEDEX, "123456789012").as_deref(),
+            Some("https://www.fedex.com/fedextrack/?trknbr=123456789012")
+        );
+        assert_eq!(
+            carriers::tracking_url(carriers::UPS, "1Z999AA10123456784").as_deref(),
+            Some("https://www.ups.com/track?tracknum=1Z999AA10123456784")
+        );
+        assert_eq!(
+            carriers::tracking_url(carriers::USPS, "9400111899223197428490").as_deref(),
+            Some("https://tools.usps.com/go/TrackConfirmAction?tLabels=9400111899223197428490")
+        );
+        assert_eq!(
+            carriers::tracking_url(carriers::DHL, "1234567890").as_deref(),
+            Some(
+                "https://www.dhl.com/global-en/home/tracking/tracking-express.html?submit=1&tracking-id=1234567890"
+            )
+        );
+
+        // Unknown carriers (and auto-detect) fall back to 17track
+        assert_eq!(
+            carriers::tracking_url(190271, "LX123456789CN").as_deref(),
+            Some("https://t.17track.net/en#nums=LX123456789CN")
+        );
+        assert_eq!(
+            carriers::tracking_url(carriers::AUTO, "AB 12&3").as_deref(),
+            Some("https://t.17track.net/en#nums=AB%2012%263")
+        );
+        assert_eq!(carriers::tracking_url(carriers::UPS, "  "), None);
+    }
+
+    #[test]
+    fn test_detect_carriers() {
+        assert_eq!(carriers::detect("1Z999AA10123456784"), vec![carriers::UPS]);
+        assert_eq!(carriers::detect("797843158299"), vec![carriers::FEDEX]);
+        // 22-digit IMpb numbers are also valid FedEx SmartPost numbers
+        assert_eq!(
+            carriers::detect("9400111899223197428497"),
+            vec![carriers::FEDEX, carriers::USPS]
+        );
+        assert!(carriers::detect("not-a-number").is_empty());
+    }
+
+    #[test]
+    fn test_cross_border_route() {
+        let json = r#"{
+            "tracking": null,
+            "latest_event": null,
+            "shipping_info": {
+                "shipper_address": {"country": "CN", "state": "Guangdong", "city": "Shenzhen"},
+                "recipient_address": {"country": "United States", "postal_code": "60455"}
+            }
+        }"#;
+        let details: ShipmentDetails = serde_json::from_str(json).unwrap();
+        assert_eq!(details.origin_country(), Some("CN"));
+        assert_eq!(details.destination_country(), Some("US"));
+
+        // Older responses use flat `i.ic` / `i.dc` codes
+        let json = r#"{"tracking": null, "latest_event": null, "i": {"ic": "GB", "dc": "de"}}"#;
+        let details: ShipmentDetails = serde_json::from_str(json).unwrap();
+        assert_eq!(details.origin_country(), Some("GB"));
+        assert_eq!(details.destination_country(), Some("DE"));
+    }
+
+    #[test]
+    fn test_required_params() {
+        let json = r#"{
+            "code": 200,
+            "number": "NUM1",
+            "carrier": 190271,
+            "params_v2": [{
+                "key": "postal_code",
+                "input_type": "text",
+                "example": "60455",
+                "regex": "^[0-9]{5}$",
+                "options": []
+            }],
+            "shipment": null
+        }"#;
+        let shipment: Shipment = serde_json::from_str(json).unwrap();
+
+        assert!(shipment.needs_params());
+        let params = shipment.required_params();
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].key, "postal_code");
+        assert_eq!(params[0].example, "60455");
+        assert_eq!(params[0].regex, "^[0-9]{5}$");
+        assert!(params[0].accepts("60455"));
+        assert!(!params[0].accepts("ABC"));
+    }
+
+    #[test]
+    fn test_tracking_request_serializes_params() {
+        let mut params = serde_json::Map::new();
+        params.insert("postal_code".to_string(), "60455".into());
+        let request = TrackingRequest {
+            data: vec![
+                TrackingItem {
+                    num: "NUM1".to_string(),
+                    fc: 190271,
+                    sc: 0,
+                    params: Some(params),
+                    tag: Some("order-1042".to_string()),
+                },
+                TrackingItem {
+                    num: "NUM2".to_string(),
+                    fc: 0,
+                    sc: 0,
+                    params: None,
+                    tag: None,
+                },
+            ],
+            guid: String::new(),
+            time_zone_offset: -480,
+            sign: "sign".to_string(),
+            lang: None,
+        };
+
+        let body: serde_json::Value = serde_json::to_value(&request).unwrap();
+        assert_eq!(body["data"][0]["params"]["postal_code"], "60455");
+        assert_eq!(body["data"][0]["tag"], "order-1042");
+        // Omitted entirely when not supplied
+        assert!(body["data"][1].get("params").is_none());
+        assert!(body["data"][1].get("tag").is_none());
+    }
+
+    #[test]
+    fn test_tracking_request_wire_body() {
+        let request = TrackingRequest {
+            data: vec![TrackingItem {
+                num: "1Z999AA10123456784".to_string(),
+                fc: 100002,
+                sc: 0,
+                params: None,
+                tag: None,
+            }],
+            guid: String::new(),
+            time_zone_offset: -480,
+            sign: "sign".to_string(),
+            lang: None,
+        };
+
+        let body = request.to_wire_body();
+        assert_eq!(
+            body,
+            r#"{"data":[{"num":"1Z999AA10123456784","fc":100002,"sc":0}],"guid":"","timeZoneOffset":-480,"sign":"sign"}"#
+        );
+
+        // The Last-Event-ID generated for the body hashes these exact bytes
+        let config = crate::last_event_id::LastEventIdConfig {
+            yq_bid: "G-EA6CFDB403493F2A".to_string(),
+            configs_md5: "1.0.156".to_string(),
+            tz_offset: 480,
+            canvas_hash: 0,
+        };
+        let value = crate::last_event_id::generate_last_event_id(&body, &config);
+        let decoded = crate::last_event_id::decode(&value).unwrap();
+        assert!(decoded.matches_body(&body));
+    }
+
+    #[test]
+    fn test_events_filtered_by_state() {
+        let details: ShipmentDetails = serde_json::from_value(serde_json::json!({
+            "tracking": {"providers": [
+                {"events": [
+                    {"time_iso": "2024-01-12T14:00:00Z", "stage": "Delivered", "description": "Delivered"},
+                    {"time_iso": "2024-01-12T08:00:00Z", "stage": "OutForDelivery", "description": "Out for delivery"},
+                    {"time_iso": "2024-01-11T09:00:00Z", "stage": "Exception", "description": "Delivery attempted"}
+                ]},
+                {"events": [
+                    {"time_iso": "2024-01-11T08:00:00Z", "stage": "OutForDelivery", "description": "Out for delivery"},
+                    {"time_iso": "2024-01-10T08:00:00Z", "stage": "InTransit", "description": "Departed facility"}
+                ]}
+            ]},
+            "latest_event": null
+        }))
+        .unwrap();
+
+        let descriptions = |events: Vec<&TrackingEvent>| {
+            events
+                .iter()
+                .map(|e| e.description.as_deref().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            descriptions(details.events()),
+            [
+                "Delivered",
+                "Out for delivery",
+                "Delivery attempted",
+                "Out for delivery",
+                "Departed facility"
+            ]
+        );
+        assert_eq!(
+            descriptions(details.events_with_state(TrackingState::Exception)),
+            ["Delivery attempted"]
+        );
+        assert_eq!(
+            details
+                .events_with_state(TrackingState::OutForDelivery)
+                .len(),
+            2
+        );
+
+        let first = details
+            .first_event_reaching(TrackingState::OutForDelivery)
+            .unwrap();
+        assert_eq!(
+            first.timestamp_rfc3339().as_deref(),
+            Some("2024-01-11T08:00:00Z")
+        );
+        assert!(
+            details
+                .first_event_reaching(TrackingState::ExceptionLost)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_outcome_hint() {
+        let hint = |json| {
+            serde_json::from_value::<Shipment>(json)
+                .unwrap()
+                .outcome_hint()
+        };
+
+        assert_eq!(
+            hint(serde_json::json!({"code": 100, "number": "NEW1", "carrier": 0})),
+            OutcomeHint::Registering
+        );
+        assert_eq!(
+            hint(serde_json::json!({
+                "code": 200, "number": "1Z999AA10123456784", "carrier": 100002,
+                "shipment": {
+                    "tracking": null,
+                    "latest_event": {"description": "Departed facility", "stage": "InTransit"}
+                }
+            })),
+            OutcomeHint::Tracking
+        );
+        assert_eq!(
+            hint(serde_json::json!({
+                "code": 400, "number": "123456789", "carrier": 0,
+                "extra": [{"multi": [100002, 100003]}]
+            })),
+            OutcomeHint::CarrierAmbiguous
+        );
+        assert_eq!(
+            hint(
+                serde_json::json!({"code": 400, "number": "NOTANUMBER", "carrier": 0, "extra": []})
+            ),
+            OutcomeHint::InvalidFormat
+        );
+        assert_eq!(
+            hint(serde_json::json!({"code": 200, "number": "QUIET1", "carrier": 100002})),
+            OutcomeHint::Unknown
+        );
+
+        assert!(OutcomeHint::InvalidFormat.is_final());
+        assert!(!OutcomeHint::Registering.is_final());
+    }
+
+    #[test]
+    fn test_package_info_missing() {
+        let info: PackageInfo = serde_json::from_str(r#"{"weight_raw": ""}"#).unwrap();
+        assert_eq!(info, PackageInfo::default());
+    }
+
+    #[test]
+    fn test_milestones() {
+        let json = r#"{
+            "tracking": null,
+            "latest_event": null,
+            "milestone": [
+                {"key_stage": "InfoReceived", "time_iso": "2024-01-10T08:00:00-05:00"},
+                {"key_stage": "InTransit", "time_iso": "2024-01-11T09:30:00-05:00"},
+                {"key_stage": "OutForDelivery", "time_iso": null},
+                {"key_stage": "Delivered", "time_iso": null}
+            ]
+        }"#;
+        let details: ShipmentDetails = serde_json::from_str(json).unwrap();
+
+        let states: Vec<TrackingState> = details.milestones().iter().map(|m| m.state).collect();
+        assert_eq!(
+            states,
+            vec![
+                TrackingState::LabelCreated,
+                TrackingState::InTransit,
+                TrackingState::OutForDelivery,
+                TrackingState::Delivered,
+            ]
+        );
+        assert_eq!(
+            details.milestones()[0].time.as_deref(),
+            Some("2024-01-10T08:00:00-05:00")
+        );
+        assert_eq!(details.milestones()[3].time, None);
+    }
+
+    #[test]
+    fn test_response_tolerates_type_drift() {
+        let response: TrackingResponse = serde_json::from_value(serde_json::json!({
+            "id": "3",
+            "guid": "abc",
+            "shipments": [
+                {"code": 200, "number": "NUM1", "carrier": 100003},
+                {"code": "200", "number": 12345, "carrier": "100002", "carrier_final": "100002"},
+                {"code": {"unexpected": true}, "number": "BAD1", "carrier": 0},
+                {"number": "BAD2", "carrier": 0}
+            ],
+            "meta": {"code": "200", "message": "Ok"}
+        }))
+        .unwrap();
+
+        assert_eq!(response.id, 3);
+        assert_eq!(response.meta.code, 200);
+        let numbers: Vec<_> = response
+            .shipments
+            .iter()
+            .map(|s| s.number.as_str())
+            .collect();
+        assert_eq!(numbers, ["NUM1", "12345"]);
+        assert_eq!(response.shipments[1].code, 200);
+        assert_eq!(response.shipments[1].resolved_carrier(), carriers::USPS);
+
+        assert_eq!(response.shipment_errors.len(), 2);
+        assert!(response.shipment_errors[0].starts_with("BAD1: "));
+        assert!(response.shipment_errors[1].starts_with("BAD2: "));
+    }
+
+    #[test]
+    fn test_meta_classify() {
+        let meta = |code: i32, message: &str| Meta {
+            code,
+            message: message.to_string(),
+        };
+        let known = [
+            (200, MetaCode::Ok),
+            (-11, MetaCode::InvalidSign),
+            (-14, MetaCode::InvalidSession),
+            (-5, MetaCode::InvalidUip),
+            (429, MetaCode::RateLimited),
+            (503, MetaCode::Maintenance),
+        ];
+        for (code, expected) in known {
+            assert_eq!(meta(code, "").classify(), expected);
+            assert_eq!(expected.code(), code);
+        }
+
+        assert_eq!(meta(-7, "Partial").classify(), MetaCode::Unknown(-7));
+        assert_eq!(MetaCode::Unknown(-7).code(), -7);
+        assert_eq!(meta(-7, "Invalid uIP").classify(), MetaCode::InvalidUip);
+    }
+
+    #[test]
+    fn test_delivery_vs_estimate() {
+        let details = |eta: serde_json::Value, events: serde_json::Value| {
+            serde_json::from_value::<ShipmentDetails>(serde_json::json!({
+                "tracking": {"providers": [{"events": events}]},
+                "time_metrics": {"estimated_delivery_date": eta},
+            }))
+            .unwrap()
+        };
+        let delivered = serde_json::json!([
+            {"time_iso": "2024-01-17T10:30:00+02:00", "stage": "Delivered"},
+            {"time_iso": "2024-01-12T09:00:00Z", "stage": "InTransit"},
+        ]);
+
+        // Delivered 08:30 UTC, the day after the window closed
+        let late = details(
+            serde_json::json!({"from": "2024-01-15T00:00:00Z", "to": "2024-01-16T12:00:00Z"}),
+            delivered.clone(),
+        );
+        assert_eq!(
+            late.delivery_vs_estimate(),
+            Some(DeliveryTiming::Late(std::time::Duration::from_secs(
+                20 * 3600 + 30 * 60
+            )))
+        );
+
+        // A bare date covers the whole day
+        let on_time = details(
+            serde_json::json!({"from": "2024-01-17", "to": "2024-01-17"}),
+            delivered.clone(),
+        );
+        assert_eq!(on_time.delivery_vs_estimate(), Some(DeliveryTiming::OnTime));
+
+        let early = details(serde_json::json!({"from": "2024-01-18"}), delivered);
+        assert_eq!(
+            early.delivery_vs_estimate(),
+            Some(DeliveryTiming::Early(std::time::Duration::from_secs(
+                15 * 3600 + 30 * 60
+            )))
+        );
+
+        let pending = details(
+            serde_json::json!({"to": "2024-01-16"}),
+            serde_json::json!([{"time_iso": "2024-01-12T09:00:00Z", "stage": "InTransit"}]),
+        );
+        assert_eq!(
+            pending.delivery_vs_estimate(),
+            Some(DeliveryTiming::NotYetDelivered)
+        );
+
+        let no_estimate = details(serde_json::json!(null), serde_json::json!([]));
+        assert_eq!(no_estimate.delivery_vs_estimate(), None);
+    }
+
+    #[test]
+    fn test_lenient_int_rejects_garbage() {
+        let shipment = |code| {
+            serde_json::from_value::<Shipment>(
+                serde_json::json!({"code": code, "number": "NUM1", "carrier": 0}),
+            )
+        };
+        assert!(shipment(serde_json::json!("abc")).is_err());
+        assert!(shipment(serde_json::json!(u64::MAX)).is_err());
+        assert_eq!(shipment(serde_json::json!(" -5 ")).unwrap().code, -5);
+    }
+
+    #[test]
+    fn test_latest_event_fingerprint() {
+        let shipment = |events: serde_json::Value| {
+            serde_json::from_value::<Shipment>(serde_json::json!({
+                "code": 200,
+                "number": "NUM1",
+                "carrier": carriers::UPS,
+                "shipment": {"tracking": {"providers": [{"events": events}]}}
+            }))
+            .unwrap()
+        };
+        let in_transit = serde_json::json!({"time_iso": "2024-01-14T08:00:00Z", "description": "Departed facility"});
+        let delivered =
+            serde_json::json!({"time_iso": "2024-01-15T10:30:00Z", "description": "Delivered"});
+
+        let first = shipment(serde_json::json!([in_transit.clone()]));
+        let fingerprint = first.shipment.as_ref().unwrap().latest_event_fingerprint();
+        assert!(fingerprint.is_some());
+        assert!(first.changed_since(None));
+
+        // Re-fetching identical data keeps it stable
+        let refetched = shipment(serde_json::json!([in_transit.clone()]));
+        assert!(!refetched.changed_since(fingerprint));
+
+        // A new event changes it
+        let updated = shipment(serde_json::json!([delivered, in_transit]));
+        assert!(updated.changed_since(fingerprint));
+    }
+
+    #[test]
+    fn test_multiple_providers() {
+        let details: ShipmentDetails = serde_json::from_value(serde_json::json!({
+            "tracking": {"providers": [
+                {
+                    "provider": {"key": "190271", "name": "Cainiao"},
+                    "events": [{"time_iso": "2024-01-10T08:00:00Z", "description": "Departed origin"}]
+                },
+                {
+                    "provider": {"key": carriers::USPS, "name": "USPS"},
+                    "events": [
+                        {"time_iso": "2024-01-15T10:30:00Z", "description": "Delivered"},
+                        {"time_iso": "2024-01-14T09:00:00Z", "description": "Arrived at facility"}
+                    ]
+                },
+                {"events": []}
+            ]}
+        }))
+        .unwrap();
+
+        let providers = details.providers();
+        assert_eq!(providers.len(), 3);
+        assert_eq!(providers[0].carrier, Some(190271));
+        assert_eq!(providers[0].name.as_deref(), Some("Cainiao"));
+        assert_eq!(providers[0].events.len(), 1);
+        assert_eq!(providers[1].carrier, Some(carriers::USPS));
+        assert_eq!(providers[1].events.len(), 2);
+        assert_eq!(providers[2].carrier, None);
+
+        // Both legs appear in the merged timeline
+        let descriptions: Vec<_> = details
+            .events()
+            .iter()
+            .map(|e| e.description.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            descriptions,
+            ["Delivered", "Arrived at facility", "Departed origin"]
+        );
+    }
+
+    #[test]
+    fn test_just_transitioned() {
+        let shipment = |prior: &str| {
+            serde_json::from_value::<Shipment>(serde_json::json!({
+                "code": 200,
+                "number": "NUM1",
+                "carrier": carriers::UPS,
+                "prior_status": prior,
+                "shipment": {
+                    "latest_event": {"time_iso": "2024-01-15T08:00:00Z", "stage": "OutForDelivery"}
+                }
+            }))
+            .unwrap()
+        };
+
+        assert_eq!(
+            shipment("InTransit").just_transitioned(),
+            Some((TrackingState::InTransit, TrackingState::OutForDelivery))
+        );
+        assert_eq!(shipment("OutForDelivery").just_transitioned(), None);
+        assert_eq!(shipment("Bogus").just_transitioned(), None);
+
+        let no_prior: Shipment = serde_json::from_value(serde_json::json!({
+            "code": 200, "number": "NUM1", "carrier": carriers::UPS, "state": "Delivered"
+        }))
+        .unwrap();
+        assert_eq!(no_prior.just_transitioned(), None);
+    }
 }
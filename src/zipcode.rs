@@ -1,22 +1,195 @@
-/// Look up city and state from a US zip code
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Resolves a country + postal code pair to a (city, state/region) pair
+pub trait LocationResolver {
+    fn resolve(&self, country: &str, postal: &str) -> Option<(String, String)>;
+}
+
+/// Default resolver backed by the offline `zipcodes` crate (US only)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UsZipResolver;
+
+impl LocationResolver for UsZipResolver {
+    fn resolve(&self, country: &str, postal: &str) -> Option<(String, String)> {
+        if country != "US" {
+            return None;
+        }
+        lookup_zipcode(postal)
+    }
+}
+
+/// Resolved zip codes, including misses; the dataset is scanned once per zip
+static ZIP_CACHE: LazyLock<Mutex<HashMap<String, Option<(String, String)>>>> =
+    LazyLock::new(Mutex::default);
+
+/// Look up city and state from a US zip code (`60455` or ZIP+4 `60455-1234`)
 pub fn lookup_zipcode(zip: &str) -> Option<(String, String)> {
-    // Avoid zipcodes::matching to suppress debug_print output.
-    let results =
-        zipcodes::filter_by(vec![|z: &zipcodes::Zipcode| z.zip_code == zip], None).ok()?;
+    let zip = zip.trim();
+    let zip = zip.split_once('-').map_or(zip, |(prefix, _)| prefix);
+    if zip.len() != 5 || !zip.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    if let Some(cached) = ZIP_CACHE.lock().unwrap().get(zip) {
+        return cached.clone();
+    }
+    let resolved = scan_zipcodes(zip);
+    ZIP_CACHE
+        .lock()
+        .unwrap()
+        .insert(zip.to_string(), resolved.clone());
+    resolved
+}
+
+fn scan_zipcodes(zip: &str) -> Option<(String, String)> {
+    // Avoid zipcodes::matching to suppress debug_print output. A failure inside
+    // the dataset lookup only costs the location, never the tracking response.
+    let results = std::panic::catch_unwind(|| {
+        zipcodes::filter_by(vec![|z: &zipcodes::Zipcode| z.zip_code == zip], None)
+    })
+    .ok()?
+    .ok()?;
     let info = results.first()?;
     Some((info.city.clone(), info.state.clone()))
 }
 
+/// ISO 3166-1 alpha-2 codes and English names of common shipping countries
+const COUNTRIES: &[(&str, &str)] = &[
+    ("AU", "Australia"),
+    ("BE", "Belgium"),
+    ("BR", "Brazil"),
+    ("CA", "Canada"),
+    ("CH", "Switzerland"),
+    ("CN", "China"),
+    ("DE", "Germany"),
+    ("ES", "Spain"),
+    ("FR", "France"),
+    ("GB", "United Kingdom"),
+    ("HK", "Hong Kong"),
+    ("IE", "Ireland"),
+    ("IN", "India"),
+    ("IT", "Italy"),
+    ("JP", "Japan"),
+    ("KR", "South Korea"),
+    ("MX", "Mexico"),
+    ("NL", "Netherlands"),
+    ("NZ", "New Zealand"),
+    ("PL", "Poland"),
+    ("SE", "Sweden"),
+    ("SG", "Singapore"),
+    ("TW", "Taiwan"),
+    ("US", "United States"),
+    ("VN", "Vietnam"),
+];
+
+/// English name for an ISO alpha-2 country code (case-insensitive)
+pub fn country_name(code: &str) -> Option<&'static str> {
+    COUNTRIES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code.trim()))
+        .map(|&(_, name)| name)
+}
+
+/// ISO alpha-2 code for a known country code or English name (case-insensitive)
+pub fn country_code(code_or_name: &str) -> Option<&'static str> {
+    let value = code_or_name.trim();
+    COUNTRIES
+        .iter()
+        .find(|(code, name)| code.eq_ignore_ascii_case(value) || name.eq_ignore_ascii_case(value))
+        .map(|&(code, _)| code)
+}
+
+/// Split a raw location like "US 60455" or "CA M5V 3L7" into (country, postal)
+pub fn parse_location(raw: &str) -> Option<(&str, String)> {
+    let (country, postal) = raw.trim().split_once(char::is_whitespace)?;
+    let postal = postal.trim();
+    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) || postal.is_empty() {
+        return None;
+    }
+    Some((country, postal.to_string()))
+}
+
 /// Format a location string, resolving US zip codes to city/state
 pub fn format_location(raw: &str) -> String {
-    // Try to parse "US ZIPCODE" format
-    let parts: Vec<&str> = raw.split_whitespace().collect();
-    if parts.len() == 2 && parts[0] == "US" {
-        let zip = parts[1];
-        if let Some((city, state)) = lookup_zipcode(zip) {
-            return format!("{}, {}", city, state);
-        }
+    format_location_with(raw, None)
+}
+
+/// Format a location string using a custom resolver (`None` uses [`UsZipResolver`])
+pub fn format_location_with(raw: &str, resolver: Option<&dyn LocationResolver>) -> String {
+    let resolver = resolver.unwrap_or(&UsZipResolver);
+    // Try to parse "COUNTRY POSTAL" format
+    if let Some((country, postal)) = parse_location(raw)
+        && let Some((city, state)) = resolver.resolve(country, &postal)
+    {
+        return format!("{}, {}", city, state);
     }
     // Fall back to raw location
     raw.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedResolver;
+
+    impl LocationResolver for FixedResolver {
+        fn resolve(&self, country: &str, postal: &str) -> Option<(String, String)> {
+            (country == "CA" && postal == "M5V 3L7")
+                .then(|| ("Toronto".to_string(), "ON".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(
+            parse_location("US 60455"),
+            Some(("US", "60455".to_string()))
+        );
+        assert_eq!(
+            parse_location("CA M5V 3L7"),
+            Some(("CA", "M5V 3L7".to_string()))
+        );
+        assert_eq!(parse_location("Chicago, IL"), None);
+    }
+
+    #[test]
+    fn test_custom_resolver() {
+        assert_eq!(
+            format_location_with("CA M5V 3L7", Some(&FixedResolver)),
+            "Toronto, ON"
+        );
+        // Unresolved locations fall back to the raw string
+        assert_eq!(
+            format_location_with("CA H0H 0H0", Some(&FixedResolver)),
+            "CA H0H 0H0"
+        );
+        // The default resolver only handles US zip codes
+        assert_eq!(format_location("CA M5V 3L7"), "CA M5V 3L7");
+    }
+
+    #[test]
+    fn test_lookup_zipcode_cached() {
+        let first = lookup_zipcode("60455");
+        assert_eq!(first, Some(("Bridgeview".to_string(), "IL".to_string())));
+        assert!(ZIP_CACHE.lock().unwrap().contains_key("60455"));
+        assert_eq!(lookup_zipcode("60455"), first);
+    }
+
+    #[test]
+    fn test_lookup_zipcode_plus_four() {
+        assert_eq!(lookup_zipcode("60455-1234"), lookup_zipcode("60455"));
+        assert!(lookup_zipcode("60455-1234").is_some());
+    }
+
+    #[test]
+    fn test_lookup_zipcode_invalid() {
+        assert_eq!(lookup_zipcode("6045"), None);
+        assert_eq!(lookup_zipcode("ABCDE"), None);
+        assert_eq!(lookup_zipcode(""), None);
+        // Well-formed but unassigned
+        assert_eq!(lookup_zipcode("00000"), None);
+        assert!(!ZIP_CACHE.lock().unwrap().contains_key("ABCDE"));
+    }
+}